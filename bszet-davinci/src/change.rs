@@ -1,47 +1,109 @@
-use anyhow::anyhow;
+use std::fmt::{Display, Formatter};
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 use sentry::protocol::Event;
 use sentry::types::Uuid;
+use serde::{Deserialize, Serialize};
+use time::{Date, Month};
 
+use crate::format::iso_date;
+use crate::kinds::{ChangeKinds, Kind};
+use crate::lessons::{LessonMode, Lessons};
+use crate::subjects::SubjectDictionary;
 use crate::timetable::{Lesson, Subject};
 use crate::REPLACEMENT_REGEX;
 
-static MOVED_FROM_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("Von .+ verschoben").unwrap());
-static MOVED_TO_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("Auf .+ verschoben").unwrap());
+static MOVE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("^(Auf|Von) (.+) verschoben$").unwrap());
+static MOVE_DATE_REGEX: Lazy<Regex> =
+  Lazy::new(|| Regex::new("(\\d{1,2})\\.(\\d{1,2})\\.(\\d{4}|\\d{2})?").unwrap());
+static MOVE_LESSONS_REGEX: Lazy<Regex> =
+  Lazy::new(|| Regex::new("(\\d+\\.?(?:\\s*-\\s*\\d+\\.?)?)\\s*(Std|Stunde|Block)").unwrap());
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Change {
   Cancel {
-    lesson: u8,
+    lessons: Lessons,
     subject: Subject,
     teachers: Vec<String>,
     place: String,
     notice: String,
   },
   PlaceChange {
-    lesson: u8,
+    lessons: Lessons,
     subject: Subject,
     teachers: Vec<String>,
     place: Replacement<String>,
     notice: String,
   },
   Addition {
-    lesson: u8,
+    lessons: Lessons,
     subject: Subject,
     teachers: Vec<String>,
     place: Option<String>,
     notice: String,
   },
   Replacement {
-    lesson: u8,
+    lessons: Lessons,
     subject: Replacement<Subject>,
     teachers: Replacement<Vec<String>>,
     place: Replacement<String>,
     notice: String,
   },
+  /// Lesson is written as exam, the place may differ
+  Exam {
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: Option<String>,
+    notice: String,
+  },
+  /// Place of the lesson is unavailable
+  RoomBlocked {
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: String,
+    notice: String,
+  },
+  /// Teacher of the lesson is absent, without substitution so far
+  TeacherAbsent {
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: String,
+    notice: String,
+  },
+  /// Lesson is supervised by `teachers`
+  Supervision {
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: String,
+    notice: String,
+  },
+  /// Lesson takes place without teacher
+  SelfStudy {
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: String,
+    notice: String,
+  },
+  /// Lesson moved to another date or block, the plan lists it at both.
+  Move {
+    /// Lessons of the row, those of `from` or `to`
+    lessons: Lessons,
+    subject: Subject,
+    teachers: Vec<String>,
+    place: String,
+    notice: String,
+    from: Slot,
+    to: Slot,
+  },
   Other {
-    lesson: u8,
+    lessons: Lessons,
     value: String,
     subject: Subject,
     teachers: Vec<String>,
@@ -50,72 +112,134 @@ pub enum Change {
   },
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// Date and lessons on one side of a [`Change::Move`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Slot {
+  #[serde(with = "iso_date")]
+  pub date: Date,
+  pub lessons: Lessons,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Replacement<T> {
   pub from: Option<T>,
   pub to: T,
 }
 
 impl Change {
+  /// Change with the [default kinds](ChangeKinds::default) and subjects.
+  #[cfg(test)]
   pub(crate) fn new(
-    lesson: u8,
+    lessons: impl Into<Lessons>,
     value: &str,
     subject: &str,
     place: String,
     teacher: &str,
     notice: Option<String>,
   ) -> anyhow::Result<Self> {
-    Ok(match value {
-      toc if (["Fällt aus", "Klasse fehlt"].contains(&toc) || MOVED_TO_REGEX.is_match(toc)) => {
-        Self::Cancel {
-          lesson,
-          subject: subject.into(),
-          place,
-          teachers: teacher
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect::<Vec<String>>(),
-          notice: notice.unwrap_or(value.to_string()),
-        }
-      }
-      "Raumänderung" => Self::PlaceChange {
-        lesson,
-        subject: subject.into(),
+    Self::with_kinds(
+      &ChangeKinds::default(),
+      &SubjectDictionary::default(),
+      lessons.into(),
+      value,
+      subject,
+      place,
+      teacher,
+      notice,
+    )
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) fn with_kinds(
+    kinds: &ChangeKinds,
+    subjects: &SubjectDictionary,
+    lessons: Lessons,
+    value: &str,
+    subject: &str,
+    place: String,
+    teacher: &str,
+    notice: Option<String>,
+  ) -> anyhow::Result<Self> {
+    let teachers = |teacher: &str| {
+      teacher
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<Vec<String>>()
+    };
+    let notice = notice.unwrap_or(value.to_string());
+
+    Ok(match kinds.get(value) {
+      Some(Kind::Cancel) => Self::Cancel {
+        lessons,
+        subject: subjects.parse(subject),
+        place,
+        teachers: teachers(teacher),
+        notice,
+      },
+      Some(Kind::PlaceChange) => Self::PlaceChange {
+        lessons,
+        subject: subjects.parse(subject),
         place: place.as_str().try_into()?,
-        teachers: teacher
-          .split(',')
-          .map(|s| s.trim().to_string())
-          .collect::<Vec<String>>(),
-        notice: notice.unwrap_or(value.to_string()),
+        teachers: teachers(teacher),
+        notice,
       },
-      "Zusatzunterricht" => Self::Addition {
-        lesson,
-        subject: subject.into(),
+      Some(Kind::Addition) => Self::Addition {
+        lessons,
+        subject: subjects.parse(subject),
         place: if place.is_empty() { None } else { Some(place) },
-        teachers: teacher
-          .split(',')
-          .map(|s| s.trim().to_string())
-          .collect::<Vec<String>>(),
-        notice: notice.unwrap_or(value.to_string()),
+        teachers: teachers(teacher),
+        notice,
       },
-      toc if toc == "Vertreten" || MOVED_FROM_REGEX.is_match(toc) => Self::Replacement {
-        lesson,
-        subject: subject.try_into()?,
+      Some(Kind::Replacement) => Self::Replacement {
+        lessons,
+        subject: replacement(subjects, subject)?,
         place: place.as_str().try_into()?,
         teachers: teacher.try_into()?,
-        notice: notice.unwrap_or(value.to_string()),
+        notice,
+      },
+      Some(Kind::Exam) => Self::Exam {
+        lessons,
+        subject: subjects.parse(current(subject)),
+        place: Some(current(&place).to_string()).filter(|place| !place.is_empty()),
+        teachers: teachers(current(teacher)),
+        notice,
+      },
+      Some(Kind::RoomBlocked) => Self::RoomBlocked {
+        lessons,
+        subject: subjects.parse(current(subject)),
+        place: current(&place).to_string(),
+        teachers: teachers(current(teacher)),
+        notice,
+      },
+      Some(Kind::TeacherAbsent) => Self::TeacherAbsent {
+        lessons,
+        subject: subjects.parse(current(subject)),
+        place: current(&place).to_string(),
+        teachers: teachers(current(teacher)),
+        notice,
+      },
+      Some(Kind::Supervision) => Self::Supervision {
+        lessons,
+        subject: subjects.parse(current(subject)),
+        place: current(&place).to_string(),
+        teachers: teachers(current(teacher)),
+        notice,
+      },
+      Some(Kind::SelfStudy) => Self::SelfStudy {
+        lessons,
+        subject: subjects.parse(current(subject)),
+        place: current(&place).to_string(),
+        teachers: teachers(current(teacher)),
+        notice,
       },
-      toc => {
+      None => {
         let change = Self::Other {
-          lesson,
-          value: toc.to_string(),
-          subject: subject.into(),
+          lessons,
+          value: value.to_string(),
+          subject: subjects.parse(subject),
           place,
-          teachers: teacher
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect::<Vec<String>>(),
-          notice: notice.unwrap_or(value.to_string()),
+          teachers: teachers(teacher),
+          notice,
         };
 
         let uuid = Uuid::new_v4();
@@ -132,124 +256,621 @@ impl Change {
     })
   }
 
-  /// Apples the change for the provided day.
-  pub(crate) fn apply(&self, lessons: &mut Vec<Lesson>) -> anyhow::Result<bool> {
-    Ok(match self {
+  /// Parses rows like `Auf 06.09. 4. Std. verschoben` at `here`, `None` if the
+  /// type of change is no move or the other date and lessons are missing.
+  #[allow(clippy::too_many_arguments)]
+  pub(crate) fn moved(
+    subjects: &SubjectDictionary,
+    here: Slot,
+    mode: LessonMode,
+    value: &str,
+    subject: &str,
+    place: &str,
+    teacher: &str,
+    notice: Option<String>,
+  ) -> Option<Self> {
+    let captures = MOVE_REGEX.captures(value.trim())?;
+    let there = parse_slot(captures.get(2).unwrap().as_str(), here.date, mode)?;
+    let (from, to) = match captures.get(1).unwrap().as_str() {
+      "Auf" => (here, there),
+      _ => (there, here),
+    };
+
+    Some(Self::Move {
+      lessons: here.lessons,
+      subject: subjects.parse(current(subject)),
+      teachers: current(teacher)
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<Vec<String>>(),
+      place: current(place).to_string(),
+      notice: notice.unwrap_or(value.to_string()),
+      from,
+      to,
+    })
+  }
+
+  /// Applies the change on `date`, moves are cancelled at their origin and added at
+  /// their destination, whichever of their rows is given.
+  pub(crate) fn apply_on(
+    &self,
+    date: &Date,
+    lessons: &mut Vec<Lesson>,
+    group: Option<&str>,
+  ) -> Outcome {
+    let Change::Move {
+      subject,
+      teachers,
+      place,
+      from,
+      to,
+      ..
+    } = self
+    else {
+      return self.apply(lessons, group);
+    };
+
+    // origin and destination are resolved before anything changes, the move is applied
+    // entirely or not at all
+    let mut cancelled = Vec::new();
+    if &from.date == date {
+      let origin = Origin {
+        subject: Some(subject),
+        place: None,
+        teachers,
+        group,
+      };
+      match find_lessons(lessons, from.lessons, &origin, false) {
+        Ok(found) => cancelled = found,
+        Err(outcome) => return outcome,
+      }
+    }
+
+    let mut added = Vec::new();
+    if &to.date == date {
+      for number in to.lessons.iter() {
+        added.push(Lesson {
+          lesson: number,
+          subject: subject.clone(),
+          planned: None,
+          iteration: None,
+          place: Some(place.to_string()).filter(|place| !place.is_empty()),
+          teachers: named(teachers),
+          group: group.map(str::to_string),
+          notice: Some(format!("Von {from} verschoben")),
+        });
+      }
+    }
+
+    for index in cancelled {
+      let lesson = &mut lessons[index];
+      lesson.subject = Subject::Cancel(Box::new(subject.clone()));
+      lesson.notice = Some(format!("Auf {to} verschoben"));
+    }
+    lessons.extend(added);
+
+    Outcome::Applied
+  }
+
+  /// Restored subjects only know their id, takes their labels from `subjects`.
+  pub(crate) fn resolve(&mut self, subjects: &SubjectDictionary) {
+    match self {
+      Change::Replacement { subject, .. } => {
+        if let Some(from) = &mut subject.from {
+          subjects.resolve(from);
+        }
+        subjects.resolve(&mut subject.to);
+      }
+      Change::Cancel { subject, .. }
+      | Change::PlaceChange { subject, .. }
+      | Change::Addition { subject, .. }
+      | Change::Exam { subject, .. }
+      | Change::RoomBlocked { subject, .. }
+      | Change::TeacherAbsent { subject, .. }
+      | Change::Supervision { subject, .. }
+      | Change::SelfStudy { subject, .. }
+      | Change::Move { subject, .. }
+      | Change::Other { subject, .. } => subjects.resolve(subject),
+    }
+  }
+
+  pub fn is_move(&self) -> bool {
+    matches!(self, Change::Move { .. })
+  }
+
+  /// Whether both changes are rows of the same move.
+  pub fn same_move(&self, other: &Change) -> bool {
+    match (self, other) {
+      (
+        Change::Move {
+          subject, from, to, ..
+        },
+        Change::Move {
+          subject: other_subject,
+          from: other_from,
+          to: other_to,
+          ..
+        },
+      ) => subject == other_subject && from == other_from && to == other_to,
+      _ => false,
+    }
+  }
+
+  /// Apples the change for the provided day, to every lesson of the range.
+  ///
+  /// `group` is the course of the row, like `1` for `LF13D_I1`.
+  pub(crate) fn apply(&self, lessons: &mut Vec<Lesson>, group: Option<&str>) -> Outcome {
+    if let Change::Addition {
+      subject,
+      teachers,
+      place,
+      notice,
+      ..
+    } = self
+    {
+      for number in self.lessons().iter() {
+        lessons.push(Lesson {
+          lesson: number,
+          subject: subject.clone(),
+          planned: None,
+          iteration: None,
+          place: place.as_ref().map(|string| string.to_string()),
+          teachers: named(teachers),
+          group: group.map(str::to_string),
+          notice: Some(notice.to_string()),
+        });
+      }
+      return Outcome::Applied;
+    }
+
+    // moves span two days and are applied by `apply_on`
+    let Some((origin, allow_cancel)) = self.origin(group) else {
+      return Outcome::NotFound;
+    };
+
+    // every lesson of the range is looked up first, the range is changed entirely or not at all
+    match find_lessons(lessons, self.lessons(), &origin, allow_cancel) {
+      Ok(found) => {
+        for index in found {
+          self.edit(&mut lessons[index]);
+        }
+        Outcome::Applied
+      }
+      Err(outcome) => outcome,
+    }
+  }
+
+  /// What the plan tells about the lessons before the change and whether they may be
+  /// cancelled already, `None` if the change edits no lessons of the timetable.
+  fn origin<'a>(&'a self, group: Option<&'a str>) -> Option<(Origin<'a>, bool)> {
+    let origin = match self {
       Change::Cancel {
-        lesson,
         subject,
-        notice,
+        teachers,
+        place,
+        ..
+      } => Origin {
+        subject: Some(subject),
+        place: Some(place),
+        teachers,
+        group,
+      },
+      Change::PlaceChange {
+        subject,
+        teachers,
+        place,
+        ..
+      } => Origin {
+        subject: Some(subject),
+        place: place.from.as_deref(),
+        teachers,
+        group,
+      },
+      Change::Replacement {
+        subject,
+        teachers,
+        place,
         ..
       } => {
-        match find_lesson(lessons, lesson, Some(subject), false)? {
-          None => false,
-          Some(lesson) => {
-            // TODO: place, teachers
-            lesson.subject = Subject::Cancel(Box::new(subject.clone()));
-            lesson.notice = Some(notice.to_string());
-            true
-          }
-        }
+        return Some((
+          Origin {
+            subject: subject.from.as_ref(),
+            place: place.from.as_deref(),
+            teachers: teachers.from.as_deref().unwrap_or_default(),
+            group,
+          },
+          true,
+        ))
       }
-      Change::PlaceChange {
-        lesson,
+      // the plan lists the place of the exam
+      Change::Exam { subject, .. } => Origin {
+        subject: stated(subject),
+        place: None,
+        teachers: &[],
+        group,
+      },
+      Change::RoomBlocked {
+        subject,
+        teachers,
+        place,
+        ..
+      }
+      | Change::TeacherAbsent {
+        subject,
+        teachers,
+        place,
+        ..
+      }
+      | Change::SelfStudy {
         subject,
+        teachers,
         place,
+        ..
+      } => Origin {
+        subject: stated(subject),
+        place: Some(place),
+        teachers,
+        group,
+      },
+      // the teachers of the plan supervise, they do not teach the lesson
+      Change::Supervision { subject, place, .. } => Origin {
+        subject: stated(subject),
+        place: Some(place),
+        teachers: &[],
+        group,
+      },
+      Change::Addition { .. } | Change::Move { .. } | Change::Other { .. } => return None,
+    };
+
+    Some((origin, false))
+  }
+
+  /// Changes a lesson of the timetable the change refers to.
+  fn edit(&self, lesson: &mut Lesson) {
+    match self {
+      Change::Cancel {
+        subject,
+        teachers,
         notice,
         ..
       } => {
-        match find_lesson(lessons, lesson, Some(subject), false)? {
-          None => false,
-          Some(lesson) => {
-            // TODO: teachers, place.from
-            lesson.place = Some(place.to.to_string());
-            lesson.notice = Some(notice.to_string());
-            true
-          }
+        if lesson.teachers.is_empty() {
+          lesson.teachers = named(teachers);
         }
+        lesson.subject = Subject::Cancel(Box::new(subject.clone()));
+        lesson.notice = Some(notice.to_string());
       }
-      Change::Addition {
-        lesson,
-        subject,
+      Change::PlaceChange {
+        teachers,
         place,
         notice,
         ..
       } => {
-        // TODO: teachers
-        lessons.push(Lesson {
-          lesson: *lesson,
-          subject: subject.clone(),
-          iteration: None,
-          place: place.as_ref().map(|string| string.to_string()),
-          notice: Some(notice.to_string()),
-        });
-        true
+        replace_teachers(lesson, teachers);
+        lesson.place = Some(place.to.to_string());
+        lesson.notice = Some(notice.to_string());
       }
       Change::Replacement {
-        lesson,
         subject,
+        teachers,
         place,
         notice,
         ..
       } => {
-        match find_lesson(lessons, lesson, subject.from.as_ref(), true)? {
-          None => false,
-          Some(lesson) => {
-            // TODO: teachers, place.from
-            lesson.subject = subject.to.clone();
-            lesson.place = Some(place.to.to_string());
-            lesson.notice = Some(notice.to_string());
-            true
-          }
+        replace_teachers(lesson, &teachers.to);
+        lesson.subject = subject.to.clone();
+        lesson.place = Some(place.to.to_string());
+        lesson.notice = Some(notice.to_string());
+      }
+      Change::Exam {
+        teachers,
+        place,
+        notice,
+        ..
+      } => {
+        replace_teachers(lesson, teachers);
+        if let Some(place) = place {
+          lesson.place = Some(place.to_string());
         }
+        lesson.notice = Some(notice.to_string());
       }
-      Change::Other { .. } => false,
-    })
+      Change::RoomBlocked { notice, .. } => {
+        lesson.place = None;
+        lesson.notice = Some(notice.to_string());
+      }
+      Change::TeacherAbsent {
+        teachers, notice, ..
+      } => {
+        if lesson.teachers.is_empty() {
+          lesson.teachers = named(teachers);
+        }
+        lesson.notice = Some(notice.to_string());
+      }
+      Change::Supervision {
+        teachers, notice, ..
+      } => {
+        replace_teachers(lesson, teachers);
+        lesson.notice = Some(notice.to_string());
+      }
+      Change::SelfStudy { notice, .. } => {
+        lesson.teachers.clear();
+        lesson.notice = Some(notice.to_string());
+      }
+      Change::Addition { .. } | Change::Move { .. } | Change::Other { .. } => {}
+    }
+  }
+  pub fn lessons(&self) -> Lessons {
+    match self {
+      Change::Cancel { lessons, .. } => *lessons,
+      Change::PlaceChange { lessons, .. } => *lessons,
+      Change::Addition { lessons, .. } => *lessons,
+      Change::Replacement { lessons, .. } => *lessons,
+      Change::Exam { lessons, .. } => *lessons,
+      Change::RoomBlocked { lessons, .. } => *lessons,
+      Change::TeacherAbsent { lessons, .. } => *lessons,
+      Change::Supervision { lessons, .. } => *lessons,
+      Change::SelfStudy { lessons, .. } => *lessons,
+      Change::Move { lessons, .. } => *lessons,
+      Change::Other { lessons, .. } => *lessons,
+    }
+  }
+
+  /// First lesson of the change.
+  pub fn lesson(&self) -> u8 {
+    self.lessons().from
   }
 
-  pub(crate) fn lesson(&self) -> u8 {
+  /// Subject of the timetable the change refers to.
+  pub fn subject(&self) -> &Subject {
     match self {
-      Change::Cancel { lesson, .. } => *lesson,
-      Change::PlaceChange { lesson, .. } => *lesson,
-      Change::Addition { lesson, .. } => *lesson,
-      Change::Replacement { lesson, .. } => *lesson,
-      Change::Other { lesson, .. } => *lesson,
+      Change::Cancel { subject, .. } => subject,
+      Change::PlaceChange { subject, .. } => subject,
+      Change::Addition { subject, .. } => subject,
+      Change::Replacement { subject, .. } => subject.from.as_ref().unwrap_or(&subject.to),
+      Change::Exam { subject, .. } => subject,
+      Change::RoomBlocked { subject, .. } => subject,
+      Change::TeacherAbsent { subject, .. } => subject,
+      Change::Supervision { subject, .. } => subject,
+      Change::SelfStudy { subject, .. } => subject,
+      Change::Move { subject, .. } => subject,
+      Change::Other { subject, .. } => subject,
     }
   }
 }
 
-fn find_lesson<'a>(
-  lessons: &'a mut [Lesson],
-  lesson: &u8,
-  subject: Option<&Subject>,
-  allow_cancel: bool,
-) -> anyhow::Result<Option<&'a mut Lesson>> {
-  let lessons = lessons
-    .iter_mut()
-    .filter(|l| &l.lesson == lesson)
-    .collect::<Vec<&mut Lesson>>();
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if let Change::Move {
+      subject, from, to, ..
+    } = self
+    {
+      return write!(f, "{subject} verschoben vom {from} auf den {to}");
+    }
 
-  match subject {
-    None => {
-      if lessons.len() == 1 {
-        Ok(lessons.into_iter().next())
-      } else {
-        Err(anyhow!("Found multiple subjects without original value to clearly identify lesson. less: {}, subjs: {:?}",lesson , lessons))
+    write!(f, "{} ", self.lessons())?;
+
+    match self {
+      Change::Cancel { subject, .. } => write!(f, "{subject} fällt aus"),
+      Change::PlaceChange { subject, place, .. } => match &place.from {
+        Some(from) => write!(f, "{subject} Raumänderung {from} → {}", place.to),
+        None => write!(f, "{subject} Raumänderung → {}", place.to),
+      },
+      Change::Addition { subject, place, .. } => match place {
+        Some(place) => write!(f, "{subject} Zusatzunterricht in {place}"),
+        None => write!(f, "{subject} Zusatzunterricht"),
+      },
+      Change::Replacement { subject, place, .. } => match &subject.from {
+        Some(from) => write!(f, "{from} vertreten durch {} in {}", subject.to, place.to),
+        None => write!(f, "{} vertreten in {}", subject.to, place.to),
+      },
+      Change::Exam { subject, place, .. } => match place {
+        Some(place) => write!(f, "{subject} Klausur in {place}"),
+        None => write!(f, "{subject} Klausur"),
+      },
+      Change::RoomBlocked { subject, place, .. } => match subject {
+        Subject::None => write!(f, "Raum {place} gesperrt"),
+        subject => write!(f, "{subject} Raum {place} gesperrt"),
+      },
+      Change::TeacherAbsent { subject, .. } => write!(f, "{subject} Lehrkraft fehlt"),
+      Change::Supervision {
+        subject, teachers, ..
+      } => {
+        write!(f, "{subject} mit Aufsicht {}", named(teachers).join(", "))
       }
+      Change::SelfStudy { subject, .. } => write!(f, "{subject} Selbststudium"),
+      Change::Move { .. } => Ok(()),
+      Change::Other { subject, value, .. } => write!(f, "{subject} {value}"),
     }
-    Some(subject) => Ok(lessons.into_iter().find(|lesson| {
-      if &lesson.subject == subject {
-        true
-      } else if allow_cancel {
-        if let Subject::Cancel(inner) = &lesson.subject {
-          inner.as_ref() == subject
-        } else {
-          false
-        }
-      } else {
-        false
+  }
+}
+
+impl Display for Slot {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{:0>2}.{:0>2}. {}",
+      self.date.day(),
+      self.date.month() as u8,
+      self.lessons
+    )
+  }
+}
+
+/// Parses the other side of a move like `06.09. 4. Std.`, the year defaults to
+/// the one closest to `date`.
+fn parse_slot(value: &str, date: Date, mode: LessonMode) -> Option<Slot> {
+  let captures = MOVE_DATE_REGEX.captures(value)?;
+  let day = captures.get(1)?.as_str().parse::<u8>().ok()?;
+  let month = Month::try_from(captures.get(2)?.as_str().parse::<u8>().ok()?).ok()?;
+
+  let other = match captures.get(3) {
+    Some(year) => {
+      let year = year.as_str().parse::<i32>().ok()?;
+      let year = if year < 100 { year + 2000 } else { year };
+      Date::from_calendar_date(year, month, day).ok()?
+    }
+    None => [date.year() - 1, date.year(), date.year() + 1]
+      .into_iter()
+      .filter_map(|year| Date::from_calendar_date(year, month, day).ok())
+      .min_by_key(|other| (*other - date).abs())?,
+  };
+
+  // the lessons follow the date
+  let rest = &value[captures.get(0)?.end()..];
+  let captures = MOVE_LESSONS_REGEX.captures(rest)?;
+  let lessons = captures.get(1)?.as_str();
+  let lessons = match captures.get(2)?.as_str() {
+    "Block" => Lessons {
+      mode: LessonMode::Blocks,
+      ..Lessons::parse(lessons, LessonMode::Periods).ok()?
+    },
+    _ => Lessons::parse(lessons, mode).ok()?,
+  };
+
+  Some(Slot {
+    date: other,
+    lessons,
+  })
+}
+
+/// New value of the replacement notation `+new (old)`.
+fn current(value: &str) -> &str {
+  match REPLACEMENT_REGEX.captures(value) {
+    Some(captures) => captures.get(1).unwrap().as_str(),
+    None => value,
+  }
+}
+
+/// Subject named by the plan, rows about a place may leave it empty.
+fn stated(subject: &Subject) -> Option<&Subject> {
+  match subject {
+    Subject::None => None,
+    subject => Some(subject),
+  }
+}
+
+/// Teachers without the empty names of an empty column.
+fn named(teachers: &[String]) -> Vec<String> {
+  teachers
+    .iter()
+    .filter(|teacher| !teacher.is_empty())
+    .cloned()
+    .collect()
+}
+
+/// Teachers of the plan take precedence over those of the timetable.
+fn replace_teachers(lesson: &mut Lesson, teachers: &[String]) {
+  let teachers = named(teachers);
+  if !teachers.is_empty() {
+    lesson.teachers = teachers;
+  }
+}
+
+/// What the plan tells about the lesson before the change.
+struct Origin<'a> {
+  subject: Option<&'a Subject>,
+  place: Option<&'a str>,
+  teachers: &'a [String],
+  group: Option<&'a str>,
+}
+
+/// Outcome of applying a change, ordered from worst to best.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Outcome {
+  /// Several lessons match the change, none was changed
+  Ambiguous(usize),
+  NotFound,
+  Applied,
+}
+
+enum Found {
+  One(usize),
+  None,
+  Ambiguous(usize),
+}
+
+/// Indices of the lessons of `range`, the worst outcome if any of them is not found.
+fn find_lessons(
+  lessons: &[Lesson],
+  range: Lessons,
+  origin: &Origin,
+  allow_cancel: bool,
+) -> Result<Vec<usize>, Outcome> {
+  let mut found = Vec::new();
+  let mut outcome = Outcome::Applied;
+
+  for number in range.iter() {
+    match find_lesson(lessons, number, origin, allow_cancel) {
+      Found::One(index) => found.push(index),
+      Found::None => outcome = outcome.min(Outcome::NotFound),
+      Found::Ambiguous(candidates) => outcome = outcome.min(Outcome::Ambiguous(candidates)),
+    }
+  }
+
+  match outcome {
+    Outcome::Applied => Ok(found),
+    outcome => Err(outcome),
+  }
+}
+
+/// Finds the lesson matching number and subject, parallel courses are told
+/// apart by their place, teachers and group.
+fn find_lesson(lessons: &[Lesson], number: u8, origin: &Origin, allow_cancel: bool) -> Found {
+  let mut candidates = lessons
+    .iter()
+    .enumerate()
+    .filter(|(_, lesson)| lesson.lesson == number)
+    .filter(|(_, lesson)| match origin.subject {
+      None => true,
+      Some(subject) => {
+        &lesson.subject == subject
+          || (allow_cancel
+            && matches!(&lesson.subject, Subject::Cancel(inner) if inner.as_ref() == subject))
       }
-    })),
+    })
+    .map(|(index, _)| index)
+    .collect::<Vec<usize>>();
+
+  // criteria which match none of the candidates are ignored, the timetable may be outdated
+  let criteria: [&dyn Fn(&Lesson) -> bool; 3] = [
+    &|lesson| match origin.place {
+      Some(place) if !place.is_empty() => lesson.place.as_deref() == Some(place),
+      _ => true,
+    },
+    &|lesson| {
+      let teachers = named(origin.teachers);
+      teachers.is_empty()
+        || lesson.teachers.is_empty()
+        || lesson
+          .teachers
+          .iter()
+          .any(|teacher| teachers.contains(teacher))
+    },
+    &|lesson| match (origin.group, &lesson.group) {
+      (Some(group), Some(lesson)) => group == lesson,
+      _ => true,
+    },
+  ];
+
+  for criterion in criteria {
+    if candidates.len() <= 1 {
+      break;
+    }
+
+    let matching = candidates
+      .iter()
+      .copied()
+      .filter(|index| criterion(&lessons[*index]))
+      .collect::<Vec<usize>>();
+    if !matching.is_empty() {
+      candidates = matching;
+    }
+  }
+
+  match candidates.as_slice() {
+    [] => Found::None,
+    [index] => Found::One(*index),
+    candidates => Found::Ambiguous(candidates.len()),
   }
 }
 
@@ -273,16 +894,13 @@ impl TryFrom<&str> for Replacement<String> {
   }
 }
 
-impl TryFrom<&str> for Replacement<Subject> {
-  type Error = anyhow::Error;
-
-  fn try_from(value: &str) -> Result<Self, Self::Error> {
-    let replacement: Replacement<String> = TryFrom::try_from(value)?;
-    Ok(Replacement {
-      from: replacement.from.map(|value| Subject::from(value.as_str())),
-      to: Subject::from(replacement.to.as_str()),
-    })
-  }
+/// Subjects of the replacement notation `+new (old)`.
+fn replacement(subjects: &SubjectDictionary, value: &str) -> anyhow::Result<Replacement<Subject>> {
+  let replacement: Replacement<String> = TryFrom::try_from(value)?;
+  Ok(Replacement {
+    from: replacement.from.map(|value| subjects.parse(&value)),
+    to: subjects.parse(&replacement.to),
+  })
 }
 
 impl TryFrom<&str> for Replacement<Vec<String>> {
@@ -305,3 +923,362 @@ impl TryFrom<&str> for Replacement<Vec<String>> {
     })
   }
 }
+
+#[cfg(test)]
+mod test {
+  use time::{Date, Month};
+
+  use crate::change::{Change, Outcome, Slot};
+  use crate::kinds::{ChangeKinds, Kind};
+  use crate::lessons::{LessonMode, Lessons};
+  use crate::subjects::SubjectDictionary;
+  use crate::timetable::{Lesson, Subject};
+
+  fn lesson(lesson: u8, subject: Subject) -> Lesson {
+    Lesson {
+      lesson,
+      planned: Some(subject.clone()),
+      subject,
+      iteration: None,
+      place: Some("B6".to_string()),
+      teachers: vec!["Mül".to_string()],
+      group: None,
+      notice: None,
+    }
+  }
+
+  #[test]
+  fn test_display() -> anyhow::Result<()> {
+    assert_eq!(
+      "2. Block D fällt aus",
+      Change::new(2, "Fällt aus", "DEU", "B6".to_string(), "Mül", None)?.to_string()
+    );
+    assert_eq!(
+      "3. Block LF 11 Raumänderung B5 → B9",
+      Change::new(
+        3,
+        "Raumänderung",
+        "LF11D",
+        "+B9 (B5)".to_string(),
+        "Mül",
+        None
+      )?
+      .to_string()
+    );
+    assert_eq!(
+      "1. Block Ma vertreten durch Ch in B11",
+      Change::new(1, "Vertreten", "+CH (MA)", "B11".to_string(), "Mül", None)?.to_string()
+    );
+
+    Ok(())
+  }
+  #[test]
+  fn test_lesson_range() -> anyhow::Result<()> {
+    let lessons = Lessons::parse("1 - 4", LessonMode::Blocks)?;
+    let change = Change::new(lessons, "Fällt aus", "DEU", "B6".to_string(), "Mül", None)?;
+    assert_eq!("1.–2. Block D fällt aus", change.to_string());
+    assert_eq!(1, change.lesson());
+
+    let mut day = vec![
+      lesson(1, Subject::id("GermanBasic")),
+      lesson(2, Subject::id("GermanBasic")),
+      lesson(3, Subject::id("Chemistry")),
+    ];
+    assert_eq!(Outcome::Applied, change.apply(&mut day, None));
+    assert_eq!(
+      Subject::Cancel(Box::new(Subject::id("GermanBasic"))),
+      day[1].subject
+    );
+    assert_eq!(Subject::id("Chemistry"), day[2].subject);
+
+    assert_eq!(vec!["Mül".to_string()], day[0].teachers);
+
+    // not applied if a lesson of the range is missing
+    let mut day = vec![lesson(1, Subject::id("GermanBasic"))];
+    assert_eq!(Outcome::NotFound, change.apply(&mut day, None));
+    assert_eq!(Subject::id("GermanBasic"), day[0].subject);
+    assert_eq!(None, day[0].notice);
+
+    let periods = Lessons::parse("5.-6.", LessonMode::Periods)?;
+    assert_eq!(
+      "5.–6. Stunde D fällt aus",
+      Change::new(periods, "Fällt aus", "DEU", "B6".to_string(), "Mül", None)?.to_string()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_teachers() -> anyhow::Result<()> {
+    let mut day = vec![
+      lesson(1, Subject::id("MathBasic")),
+      lesson(2, Subject::id("GermanBasic")),
+    ];
+    day[1].teachers.clear();
+
+    Change::new(
+      1,
+      "Vertreten",
+      "+CH (MA)",
+      "+B11 (B6)".to_string(),
+      "+Sch (Mül)",
+      None,
+    )?
+    .apply(&mut day, None);
+    assert_eq!(vec!["Sch".to_string()], day[0].teachers);
+    // the subject of the timetable is kept apart from the replacement
+    assert_eq!(Subject::id("Chemistry"), day[0].subject);
+    assert_eq!(Some(Subject::id("MathBasic")), day[0].planned);
+
+    // the absent teacher of a cancelled lesson is taken from the plan
+    Change::new(2, "Fällt aus", "DEU", "B6".to_string(), "Kol", None)?.apply(&mut day, None);
+    assert_eq!(vec!["Kol".to_string()], day[1].teachers);
+
+    Change::new(
+      3,
+      "Zusatzunterricht",
+      "CH",
+      "B9".to_string(),
+      "Sch, Kol",
+      None,
+    )?
+    .apply(&mut day, None);
+    assert_eq!(vec!["Sch".to_string(), "Kol".to_string()], day[2].teachers);
+    assert_eq!(None, day[2].planned);
+
+    // no teacher in the plan keeps the one of the timetable
+    Change::new(1, "Raumänderung", "CH", "+B12 (B11)".to_string(), "", None)?.apply(&mut day, None);
+    assert_eq!(vec!["Sch".to_string()], day[0].teachers);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parallel_courses() -> anyhow::Result<()> {
+    let course = |subject, place: &str, teacher: &str, group: &str| Lesson {
+      group: Some(group.to_string()),
+      place: Some(place.to_string()),
+      teachers: vec![teacher.to_string()],
+      ..lesson(3, subject)
+    };
+    let day = || {
+      vec![
+        course(Subject::id("Lf10"), "B5", "Mül", "1"),
+        course(Subject::id("Lf10"), "B3", "Kol", "2"),
+      ]
+    };
+
+    // by original place
+    let mut tuesday = day();
+    let change = Change::new(
+      3,
+      "Raumänderung",
+      "LF10D_I2",
+      "+B12 (B3)".to_string(),
+      "",
+      None,
+    )?;
+    assert_eq!(Outcome::Applied, change.apply(&mut tuesday, None));
+    assert_eq!(Some("B5"), tuesday[0].place.as_deref());
+    assert_eq!(Some("B12"), tuesday[1].place.as_deref());
+
+    // by teacher
+    let mut tuesday = day();
+    let change = Change::new(3, "Fällt aus", "LF10D_I1", "".to_string(), "Mül", None)?;
+    assert_eq!(Outcome::Applied, change.apply(&mut tuesday, None));
+    assert_eq!(
+      Subject::Cancel(Box::new(Subject::id("Lf10"))),
+      tuesday[0].subject
+    );
+    assert_eq!(Subject::id("Lf10"), tuesday[1].subject);
+
+    // by group
+    let mut tuesday = day();
+    let change = Change::new(3, "Fällt aus", "LF10D_I2", "".to_string(), "", None)?;
+    assert_eq!(Outcome::Applied, change.apply(&mut tuesday, Some("2")));
+    assert_eq!(Subject::id("Lf10"), tuesday[0].subject);
+    assert_eq!(
+      Subject::Cancel(Box::new(Subject::id("Lf10"))),
+      tuesday[1].subject
+    );
+
+    // nothing to tell the courses apart
+    let mut tuesday = day();
+    assert_eq!(Outcome::Ambiguous(2), change.apply(&mut tuesday, None));
+    assert_eq!(Subject::id("Lf10"), tuesday[0].subject);
+    assert_eq!(Subject::id("Lf10"), tuesday[1].subject);
+
+    let change = Change::new(3, "Vertreten", "CH", "B9".to_string(), "Sch", None)?;
+    assert_eq!(Outcome::Ambiguous(2), change.apply(&mut day(), None));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_move() -> anyhow::Result<()> {
+    let december = Date::from_calendar_date(2023, Month::December, 20)?;
+    let here = Slot {
+      date: december,
+      lessons: Lessons::from(1),
+    };
+    let moved = |value: &str| {
+      Change::moved(
+        &SubjectDictionary::default(),
+        here,
+        LessonMode::Blocks,
+        value,
+        "+MA (MA)",
+        "+B11 (B6)",
+        "Mül",
+        None,
+      )
+    };
+
+    // the year is taken from the closest date
+    let change = moved("Auf Mo, 08.01. 5.-6. Std. verschoben").unwrap();
+    assert_eq!(
+      "Ma verschoben vom 20.12. 1. Block auf den 08.01. 3. Block",
+      change.to_string()
+    );
+    let Change::Move {
+      from, to, place, ..
+    } = &change
+    else {
+      panic!("expected move, got {change:?}");
+    };
+    assert_eq!(&here, from);
+    assert_eq!(Date::from_calendar_date(2024, Month::January, 8)?, to.date);
+    assert_eq!("B11", place);
+
+    let change = moved("Von 18.12.2023 2. Block verschoben").unwrap();
+    assert_eq!(
+      "Ma verschoben vom 18.12. 2. Block auf den 20.12. 1. Block",
+      change.to_string()
+    );
+    assert!(!change.same_move(&moved("Von 18.12.2023 3. Block verschoben").unwrap()));
+
+    assert!(moved("Auf später verschoben").is_none());
+    assert!(moved("Fällt aus").is_none());
+
+    // a single row moves the lesson on both days
+    let change = moved("Auf 21.12. 3.-4. Std. verschoben").unwrap();
+    let mut day = vec![lesson(1, Subject::id("MathBasic"))];
+    assert_eq!(Outcome::Applied, change.apply_on(&december, &mut day, None));
+    assert_eq!(
+      Subject::Cancel(Box::new(Subject::id("MathBasic"))),
+      day[0].subject
+    );
+    assert_eq!(
+      Some("Auf 21.12. 2. Block verschoben"),
+      day[0].notice.as_deref()
+    );
+
+    let mut day = Vec::new();
+    let next = december + time::Duration::days(1);
+    assert_eq!(Outcome::Applied, change.apply_on(&next, &mut day, None));
+    assert_eq!(2, day[0].lesson);
+    assert_eq!(Subject::id("MathBasic"), day[0].subject);
+
+    // a move whose lessons are not all found changes neither of its days
+    let change = Change::moved(
+      &SubjectDictionary::default(),
+      Slot {
+        date: december,
+        lessons: Lessons::parse("1 - 4", LessonMode::Blocks)?,
+      },
+      LessonMode::Blocks,
+      "Auf 20.12. 5. Block verschoben",
+      "MA",
+      "B6",
+      "Mül",
+      None,
+    )
+    .unwrap();
+    let mut day = vec![lesson(1, Subject::id("MathBasic"))];
+    assert_eq!(
+      Outcome::NotFound,
+      change.apply_on(&december, &mut day, None)
+    );
+    assert_eq!(1, day.len());
+    assert_eq!(Subject::id("MathBasic"), day[0].subject);
+    assert_eq!(None, day[0].notice);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_kinds() -> anyhow::Result<()> {
+    let kinds = ChangeKinds::from_iter([
+      ("Klausur", Kind::Exam),
+      ("Raum gesperrt", Kind::RoomBlocked),
+      ("Lehrkraft fehlt", Kind::TeacherAbsent),
+      ("Aufsicht", Kind::Supervision),
+      ("Selbststudium", Kind::SelfStudy),
+    ]);
+    let subjects = SubjectDictionary::default();
+    let change = |value: &str, subject: &str, place: &str, teacher: &str| {
+      Change::with_kinds(
+        &kinds,
+        &subjects,
+        Lessons::from(1),
+        value,
+        subject,
+        place.to_string(),
+        teacher,
+        None,
+      )
+    };
+    let day = || {
+      vec![
+        lesson(1, Subject::id("MathBasic")),
+        lesson(2, Subject::id("GermanBasic")),
+      ]
+    };
+
+    let exam = change("Klausur", "MA", "+A102 (B6)", "Sch, Kol")?;
+    assert_eq!("1. Block Ma Klausur in A102", exam.to_string());
+    let mut monday = day();
+    assert_eq!(Outcome::Applied, exam.apply(&mut monday, None));
+    assert_eq!(Subject::id("MathBasic"), monday[0].subject);
+    assert_eq!(Some("A102"), monday[0].place.as_deref());
+    assert_eq!(
+      vec!["Sch".to_string(), "Kol".to_string()],
+      monday[0].teachers
+    );
+    assert_eq!(Some("Klausur"), monday[0].notice.as_deref());
+
+    // the room alone identifies the lesson
+    let blocked = change("Raum gesperrt", "", "B6", "")?;
+    assert_eq!("1. Block Raum B6 gesperrt", blocked.to_string());
+    let mut monday = day();
+    assert_eq!(Outcome::Applied, blocked.apply(&mut monday, None));
+    assert_eq!(None, monday[0].place);
+
+    let absent = change("Lehrkraft fehlt", "MA", "B6", "Mül")?;
+    assert_eq!("1. Block Ma Lehrkraft fehlt", absent.to_string());
+    let mut monday = day();
+    assert_eq!(Outcome::Applied, absent.apply(&mut monday, None));
+    assert_eq!(vec!["Mül".to_string()], monday[0].teachers);
+    assert_eq!(Some("Lehrkraft fehlt"), monday[0].notice.as_deref());
+
+    let supervision = change("Aufsicht", "MA", "B6", "Wei")?;
+    assert_eq!("1. Block Ma mit Aufsicht Wei", supervision.to_string());
+    let mut monday = day();
+    assert_eq!(Outcome::Applied, supervision.apply(&mut monday, None));
+    assert_eq!(vec!["Wei".to_string()], monday[0].teachers);
+
+    let self_study = change("Selbststudium", "MA", "B6", "Mül")?;
+    assert_eq!("1. Block Ma Selbststudium", self_study.to_string());
+    let mut monday = day();
+    assert_eq!(Outcome::Applied, self_study.apply(&mut monday, None));
+    assert!(monday[0].teachers.is_empty());
+    assert_eq!(Subject::id("MathBasic"), monday[0].subject);
+
+    // unmapped types of change stay unapplied
+    let other = change("Wandertag", "MA", "", "")?;
+    assert!(matches!(other, Change::Other { .. }));
+    assert_eq!(Outcome::NotFound, other.apply(&mut day(), None));
+
+    Ok(())
+  }
+}
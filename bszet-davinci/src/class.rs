@@ -0,0 +1,101 @@
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
+
+/// A school class, DaVinci is not consistent in spelling class names,
+/// e.g. `IGD21` and `IGD 21` are both used for the same class.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Class {
+  pub name: String,
+  pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ClassRegistry {
+  classes: Vec<Class>,
+}
+
+impl Class {
+  pub fn new(name: String, aliases: Vec<String>) -> Self {
+    Self { name, aliases }
+  }
+
+  /// Canonical name followed by all aliases.
+  pub fn spellings(&self) -> impl Iterator<Item = &str> {
+    std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+  }
+
+  pub fn is(&self, value: &str) -> bool {
+    self.spellings().any(|spelling| spelling == value)
+  }
+
+  /// Whether any of the class names of a row refer to this class.
+  pub fn affected_by(&self, classes: &[String]) -> bool {
+    classes.iter().any(|class| self.is(class))
+  }
+}
+
+impl Display for Class {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)
+  }
+}
+
+impl ClassRegistry {
+  pub fn insert(&mut self, class: Class) -> anyhow::Result<()> {
+    for spelling in class.spellings() {
+      if let Some(existing) = self.resolve(spelling) {
+        return Err(anyhow!(
+          "Class name {:?} of {} is already used by {}",
+          spelling,
+          class,
+          existing
+        ));
+      }
+    }
+
+    self.classes.push(class);
+    Ok(())
+  }
+
+  /// Finds the class by its canonical name or one of its aliases.
+  pub fn resolve(&self, value: &str) -> Option<&Class> {
+    self.classes.iter().find(|class| class.is(value))
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Class> {
+    self.classes.iter()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::class::{Class, ClassRegistry};
+
+  #[test]
+  fn test_resolve() -> anyhow::Result<()> {
+    let mut registry = ClassRegistry::default();
+    registry.insert(Class::new("IGD21".to_string(), vec!["IGD 21".to_string()]))?;
+    registry.insert(Class::new("IGD22".to_string(), vec![]))?;
+
+    assert_eq!("IGD21", registry.resolve("IGD 21").unwrap().name);
+    assert_eq!("IGD21", registry.resolve("IGD21").unwrap().name);
+    assert_eq!("IGD22", registry.resolve("IGD22").unwrap().name);
+    assert!(registry.resolve("IGD 22").is_none());
+
+    assert!(registry
+      .insert(Class::new("IGD23".to_string(), vec!["IGD 21".to_string()]))
+      .is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_affected_by() {
+    let class = Class::new("IGD21".to_string(), vec!["IGD 21".to_string()]);
+
+    assert!(class.affected_by(&["IGD 21".to_string()]));
+    assert!(class.affected_by(&["IGD20".to_string(), "IGD21".to_string()]));
+    assert!(!class.affected_by(&["IGD22".to_string()]));
+  }
+}
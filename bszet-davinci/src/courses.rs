@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::subjects::SubjectDictionary;
+use crate::timetable::{Lesson, Subject, Timetable};
+use crate::Row;
+
+static GROUP_REGEX: Lazy<Regex> =
+  Lazy::new(|| Regex::new("^(?i:gruppe|group)\\s*(\\d+)$").unwrap());
+
+/// Courses a student attends, like `LK-Ma, En, Ru, Gruppe 2`.
+///
+/// Without subjects all electives are kept, without group all groups.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Courses {
+  pub subjects: Vec<Subject>,
+  pub group: Option<String>,
+}
+
+impl Courses {
+  pub fn is_empty(&self) -> bool {
+    self.subjects.is_empty() && self.group.is_none()
+  }
+
+  /// Whether the lesson is attended, `electives` are the subjects only some students attend.
+  pub fn keeps(&self, lesson: &Lesson, electives: &HashSet<Subject>) -> bool {
+    let subject = match &lesson.subject {
+      Subject::Cancel(subject) => subject,
+      subject => subject,
+    };
+
+    self.keeps_group(lesson.group.as_deref()) && self.keeps_subject(subject, electives)
+  }
+
+  /// Whether the row of the substitution plan is relevant for the courses.
+  pub fn affects(&self, row: &Row, electives: &HashSet<Subject>) -> bool {
+    self.keeps_group(row.group()) && self.keeps_subject(row.change.subject(), electives)
+  }
+
+  /// Ids of the selected subjects, unlike the labels they do not change.
+  pub fn subject_ids(&self) -> Vec<String> {
+    self
+      .subjects
+      .iter()
+      .filter_map(|subject| match subject {
+        Subject::Known(info) => Some(info.id.clone()),
+        _ => None,
+      })
+      .collect()
+  }
+
+  /// Courses of stored subject ids, returns the ids missing in the dictionary separately.
+  pub fn from_ids(
+    ids: &[String],
+    group: Option<String>,
+    subjects: &SubjectDictionary,
+  ) -> (Self, Vec<String>) {
+    let mut courses = Self {
+      subjects: Vec::new(),
+      group,
+    };
+    let mut unknown = Vec::new();
+
+    for id in ids {
+      match subjects.get(id) {
+        Some(subject) => courses.subjects.push(Subject::Known(subject.clone())),
+        None => unknown.push(id.clone()),
+      }
+    }
+
+    (courses, unknown)
+  }
+
+  /// Parses courses like `LK-Ma, En, Ru, Gruppe 2`, by code or label of the subjects.
+  pub fn parse(value: &str, subjects: &SubjectDictionary) -> anyhow::Result<Self> {
+    let (courses, unknown) = Self::parse_known(value, subjects)?;
+    match unknown.first() {
+      Some(item) => Err(anyhow!("Unknown course {item}")),
+      None => Ok(courses),
+    }
+  }
+
+  /// Like [`Courses::parse`], but unknown courses are left out and returned separately.
+  pub fn parse_known(
+    value: &str,
+    subjects: &SubjectDictionary,
+  ) -> anyhow::Result<(Self, Vec<String>)> {
+    let mut courses = Self::default();
+    let mut unknown = Vec::new();
+
+    for item in value
+      .split(',')
+      .map(str::trim)
+      .filter(|item| !item.is_empty())
+    {
+      if let Some(captures) = GROUP_REGEX.captures(item) {
+        let group = captures.get(1).unwrap().as_str().to_string();
+        if courses.group.replace(group).is_some() {
+          return Err(anyhow!("Only one group can be selected"));
+        }
+        continue;
+      }
+
+      match parse_subject(item, subjects) {
+        Some(subject) if !courses.subjects.contains(&subject) => courses.subjects.push(subject),
+        Some(_) => {}
+        None => unknown.push(item.to_string()),
+      }
+    }
+
+    Ok((courses, unknown))
+  }
+
+  fn keeps_group(&self, group: Option<&str>) -> bool {
+    match (&self.group, group) {
+      (Some(selected), Some(group)) => selected == group,
+      _ => true,
+    }
+  }
+
+  fn keeps_subject(&self, subject: &Subject, electives: &HashSet<Subject>) -> bool {
+    self.subjects.is_empty() || !electives.contains(subject) || self.subjects.contains(subject)
+  }
+}
+
+/// Subjects taught in parallel to other subjects, lessons split by group are not considered.
+pub(crate) fn electives(timetable: &Timetable) -> HashSet<Subject> {
+  let mut electives = HashSet::new();
+
+  for day in timetable.values() {
+    let lessons = day
+      .iter()
+      .filter(|lesson| lesson.group.is_none())
+      .collect::<Vec<&Lesson>>();
+
+    for lesson in &lessons {
+      let parallel = lessons.iter().any(|other| {
+        other.lesson == lesson.lesson
+          && other.subject != lesson.subject
+          && (other.iteration.is_none()
+            || lesson.iteration.is_none()
+            || other.iteration == lesson.iteration)
+      });
+
+      if parallel {
+        electives.insert(lesson.subject.clone());
+      }
+    }
+  }
+
+  electives
+}
+
+/// Subjects which can be selected, by code like `LK-MA` or by name like `LK-Ma`.
+fn parse_subject(value: &str, subjects: &SubjectDictionary) -> Option<Subject> {
+  subjects
+    .by_code(&value.to_uppercase())
+    .or_else(|| {
+      subjects
+        .iter()
+        .find(|subject| subject.short.eq_ignore_ascii_case(value))
+    })
+    .filter(|subject| subject.selectable)
+    .map(|subject| Subject::Known(subject.clone()))
+}
+
+impl Display for Courses {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let mut items = self
+      .subjects
+      .iter()
+      .map(Subject::to_string)
+      .collect::<Vec<String>>();
+    if let Some(group) = &self.group {
+      items.push(format!("Gruppe {group}"));
+    }
+
+    f.write_str(&items.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashSet;
+
+  use crate::courses::{electives, Courses};
+  use crate::subjects::SubjectDictionary;
+  use crate::timetable::{Lesson, Subject, Timetable};
+  use time::Weekday;
+
+  fn lesson(lesson: u8, subject: Subject, group: Option<&str>) -> Lesson {
+    Lesson {
+      lesson,
+      planned: Some(subject.clone()),
+      subject,
+      iteration: None,
+      place: None,
+      teachers: Vec::new(),
+      group: group.map(str::to_string),
+      notice: None,
+    }
+  }
+
+  #[test]
+  fn test_parse() -> anyhow::Result<()> {
+    let subjects = SubjectDictionary::default();
+    let parse = |value: &str| Courses::parse(value, &subjects);
+
+    let courses = parse("LK-Ma, En, R-B, gruppe 2")?;
+    assert_eq!(
+      vec![
+        Subject::id("MathAdvanced"),
+        Subject::id("EnglishBasic"),
+        Subject::id("Russian")
+      ],
+      courses.subjects
+    );
+    assert_eq!(Some("2"), courses.group.as_deref());
+    assert_eq!("LK-Ma, En, Ru, Gruppe 2", courses.to_string());
+    assert_eq!(courses, parse(&courses.to_string())?);
+
+    assert!(parse("")?.is_empty());
+    assert!(parse("Ma, Sanskrit").is_err());
+    assert!(parse("Gruppe 1, Gruppe 2").is_err());
+
+    let (courses, unknown) = Courses::parse_known("Ma, Sanskrit, Gruppe 1", &subjects)?;
+    assert_eq!("Ma, Gruppe 1", courses.to_string());
+    assert_eq!(vec!["Sanskrit".to_string()], unknown);
+
+    let (courses, unknown) = Courses::from_ids(
+      &["MathAdvanced".to_string(), "Sanskrit".to_string()],
+      None,
+      &subjects,
+    );
+    assert_eq!(vec!["MathAdvanced".to_string()], courses.subject_ids());
+    assert_eq!(vec!["Sanskrit".to_string()], unknown);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_keeps() -> anyhow::Result<()> {
+    let timetable = Timetable::from([(
+      Weekday::Tuesday,
+      vec![
+        lesson(1, Subject::id("MathBasic"), None),
+        lesson(1, Subject::id("EnglishBasic"), None),
+        lesson(2, Subject::id("GermanBasic"), None),
+        lesson(3, Subject::id("Lf13"), Some("1")),
+        lesson(3, Subject::id("Lf11"), Some("2")),
+      ],
+    )]);
+    let electives = electives(&timetable);
+    assert_eq!(
+      HashSet::from([Subject::id("MathBasic"), Subject::id("EnglishBasic")]),
+      electives
+    );
+
+    let subjects = SubjectDictionary::default();
+    let kept = |courses: &str| -> anyhow::Result<Vec<Subject>> {
+      let courses = Courses::parse(courses, &subjects)?;
+      Ok(
+        timetable[&Weekday::Tuesday]
+          .iter()
+          .filter(|lesson| courses.keeps(lesson, &electives))
+          .map(|lesson| lesson.subject.clone())
+          .collect(),
+      )
+    };
+
+    assert_eq!(5, kept("")?.len());
+    assert_eq!(
+      vec![
+        Subject::id("EnglishBasic"),
+        Subject::id("GermanBasic"),
+        Subject::id("Lf11")
+      ],
+      kept("En, Gruppe 2")?
+    );
+    assert_eq!(
+      vec![
+        Subject::id("MathBasic"),
+        Subject::id("EnglishBasic"),
+        Subject::id("GermanBasic"),
+        Subject::id("Lf13")
+      ],
+      kept("Gruppe 1")?
+    );
+
+    let mut cancelled = lesson(1, Subject::Cancel(Box::new(Subject::id("MathBasic"))), None);
+    assert!(!Courses::parse("En", &subjects)?.keeps(&cancelled, &electives));
+    cancelled.subject = Subject::Cancel(Box::new(Subject::id("EnglishBasic")));
+    assert!(Courses::parse("En", &subjects)?.keeps(&cancelled, &electives));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+use std::ops::AddAssign;
+use std::time::Duration;
+
+use reqwest::header::HeaderValue;
+use reqwest::{StatusCode, Url};
+use serde::Serialize;
+use time::{Date, OffsetDateTime};
+
+use crate::diff::Diff;
+use crate::Row;
+
+/// Snapshots with fewer rows are suspicious only above this size.
+const MIN_ROWS: usize = 10;
+
+/// Parsed page of the substitution plan, reused while the server answers with
+/// `304 Not Modified`.
+pub(crate) struct CachedPage {
+  pub(crate) etag: Option<HeaderValue>,
+  /// Raw header, sent back as `If-Modified-Since`
+  pub(crate) last_modified_header: HeaderValue,
+  pub(crate) last_modified: OffsetDateTime,
+  pub(crate) rows: Vec<Row>,
+  /// Last row of the previous page if the first row continues it, the page is parsed again
+  /// once that row changes
+  pub(crate) continued: Option<Row>,
+  pub(crate) next: Option<Url>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CrawlStats {
+  /// Pages of the substitution plan
+  pub pages: u64,
+  /// Pages downloaded and parsed
+  pub fetched: u64,
+  /// Pages answered with `304 Not Modified`
+  pub not_modified: u64,
+  /// Size of the downloaded response bodies
+  pub bytes: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Stats {
+  pub crawls: u64,
+  pub partial: u64,
+  pub failed: u64,
+  #[serde(with = "time::serde::rfc3339::option")]
+  pub last_crawl: Option<OffsetDateTime>,
+  /// Statistics of the last crawl
+  pub last: CrawlStats,
+  /// Statistics of all crawls since the start
+  pub total: CrawlStats,
+}
+
+impl AddAssign for CrawlStats {
+  fn add_assign(&mut self, other: Self) {
+    self.pages += other.pages;
+    self.fetched += other.fetched;
+    self.not_modified += other.not_modified;
+    self.bytes += other.bytes;
+  }
+}
+
+/// Result of [`crate::Davinci::update`].
+#[derive(Debug)]
+pub enum CrawlOutcome {
+  /// All pages were crawled and the snapshot was replaced
+  Complete(Diff),
+  /// The crawl is incomplete or suspicious, the previous snapshot is kept
+  Partial(Incomplete),
+  /// Not even the first page could be crawled
+  Failed(anyhow::Error),
+}
+
+#[derive(Debug)]
+pub enum Incomplete {
+  /// A page failed after all retries, `pages` pages were crawled before
+  PageFailed {
+    url: Url,
+    pages: u64,
+    error: anyhow::Error,
+  },
+  /// Dates of the previous snapshot which are missing although later dates are present
+  MissingDates(Vec<Date>),
+  /// Drastically fewer rows for the dates still on the plan
+  Shrunk { before: usize, after: usize },
+}
+
+/// How often a page is requested before the crawl is given up.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+  pub attempts: u32,
+  /// Delay before the first retry, doubled for every further retry
+  pub backoff: Duration,
+  /// Suspicious snapshots are accepted once they were crawled this often in a row
+  pub confirmations: u32,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      attempts: 4,
+      backoff: Duration::from_secs(1),
+      confirmations: 3,
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Delay before retry `attempt`, starting with 1.
+  pub(crate) fn delay(&self, attempt: u32) -> Duration {
+    self.backoff * 2u32.saturating_pow(attempt - 1)
+  }
+}
+
+impl CrawlOutcome {
+  pub fn diff(&self) -> Option<&Diff> {
+    match self {
+      CrawlOutcome::Complete(diff) => Some(diff),
+      _ => None,
+    }
+  }
+
+  /// The difference of a complete crawl, an error otherwise.
+  pub fn into_result(self) -> anyhow::Result<Diff> {
+    match self {
+      CrawlOutcome::Complete(diff) => Ok(diff),
+      CrawlOutcome::Partial(incomplete) => Err(anyhow::anyhow!("Partial crawl: {incomplete}")),
+      CrawlOutcome::Failed(err) => Err(err),
+    }
+  }
+}
+
+impl Display for Incomplete {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Incomplete::PageFailed { url, pages, error } => {
+        write!(f, "{url} failed after {pages} pages: {error}")
+      }
+      Incomplete::MissingDates(dates) => {
+        write!(f, "dates missing: ")?;
+        for (i, date) in dates.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{date}")?;
+        }
+        Ok(())
+      }
+      Incomplete::Shrunk { before, after } => {
+        write!(f, "shrunk from {before} to {after} rows")
+      }
+    }
+  }
+}
+
+/// Whether a failed request may succeed when repeated.
+pub(crate) fn is_transient(err: &reqwest::Error) -> bool {
+  !matches!(
+    err.status(),
+    Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+  )
+}
+
+/// Checks whether `after` lost dates or rows compared to `before`.
+///
+/// Past dates are dropped from the front of the plan, so only dates following
+/// the first date of `after` are expected to be present.
+pub(crate) fn check_shrinkage(before: &HashSet<Row>, after: &HashSet<Row>) -> Option<Incomplete> {
+  let first = after.iter().map(|row| row.date).min();
+  let relevant = |row: &&Row| first.map(|first| row.date >= first).unwrap_or(true);
+
+  let dates = after.iter().map(|row| row.date).collect::<HashSet<Date>>();
+  let missing = before
+    .iter()
+    .filter(relevant)
+    .map(|row| row.date)
+    .filter(|date| !dates.contains(date))
+    .collect::<BTreeSet<Date>>();
+
+  if !missing.is_empty() {
+    return Some(Incomplete::MissingDates(missing.into_iter().collect()));
+  }
+
+  let before = before.iter().filter(relevant).count();
+  if before >= MIN_ROWS && after.len() < before / 2 {
+    return Some(Incomplete::Shrunk {
+      before,
+      after: after.len(),
+    });
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashSet;
+
+  use time::macros::date;
+  use time::Date;
+
+  use crate::change::Change;
+  use crate::crawl::{check_shrinkage, Incomplete, RetryPolicy};
+  use crate::timetable::Subject;
+  use crate::Row;
+
+  fn rows(dates: &[(Date, u8)]) -> HashSet<Row> {
+    dates
+      .iter()
+      .flat_map(|(date, lessons)| {
+        (1..=*lessons).map(move |lesson| Row {
+          index: lesson,
+          date: *date,
+          class: vec!["IGD21".to_string()],
+          change: Change::Cancel {
+            lessons: lesson.into(),
+            subject: Subject::id("Chemistry"),
+            teachers: vec!["Kol".to_string()],
+            place: "B11".to_string(),
+            notice: String::new(),
+          },
+          raw: vec![],
+        })
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_shrinkage() {
+    let monday = date!(2023 - 09 - 04);
+    let tuesday = date!(2023 - 09 - 05);
+    let wednesday = date!(2023 - 09 - 06);
+
+    let before = rows(&[(monday, 6), (tuesday, 6), (wednesday, 6)]);
+
+    // monday is over
+    assert!(check_shrinkage(&before, &rows(&[(tuesday, 6), (wednesday, 1)])).is_none());
+    assert!(check_shrinkage(
+      &before,
+      &rows(&[(tuesday, 2), (wednesday, 4), (date!(2023 - 09 - 07), 1)])
+    )
+    .is_none());
+
+    assert!(matches!(
+      check_shrinkage(&before, &rows(&[(monday, 6), (wednesday, 6)])),
+      Some(Incomplete::MissingDates(dates)) if dates == vec![tuesday]
+    ));
+    assert!(matches!(
+      check_shrinkage(&before, &rows(&[(tuesday, 6)])),
+      Some(Incomplete::MissingDates(dates)) if dates == vec![wednesday]
+    ));
+    assert!(matches!(
+      check_shrinkage(&before, &HashSet::new()),
+      Some(Incomplete::MissingDates(dates)) if dates.len() == 3
+    ));
+    assert!(matches!(
+      check_shrinkage(&before, &rows(&[(monday, 1), (tuesday, 2), (wednesday, 2)])),
+      Some(Incomplete::Shrunk {
+        before: 18,
+        after: 5
+      })
+    ));
+
+    // small plans may shrink
+    let before = rows(&[(monday, 4), (tuesday, 4)]);
+    assert!(check_shrinkage(&before, &rows(&[(monday, 1), (tuesday, 1)])).is_none());
+  }
+
+  #[test]
+  fn test_backoff() {
+    let policy = RetryPolicy::default();
+    assert_eq!(1, policy.delay(1).as_secs());
+    assert_eq!(2, policy.delay(2).as_secs());
+    assert_eq!(4, policy.delay(3).as_secs());
+  }
+}
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use crate::lessons::Lessons;
+use crate::Row;
+
+/// Difference between two consecutive snapshots of the substitution plan.
+///
+/// Rows which were removed and added for the same date, class and lesson are
+/// reported as modification instead.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Diff {
+  pub added: Vec<Row>,
+  pub removed: Vec<Row>,
+  pub modified: Vec<Modification>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Modification {
+  pub before: Row,
+  pub after: Row,
+}
+
+type Key = (Date, Vec<String>, Lessons);
+
+fn key(row: &Row) -> Key {
+  (row.date, row.class.clone(), row.change.lessons())
+}
+
+impl Diff {
+  pub fn between(before: &HashSet<Row>, after: &HashSet<Row>) -> Self {
+    let mut removed = BTreeMap::<Key, Vec<&Row>>::new();
+    for row in before.difference(after) {
+      removed.entry(key(row)).or_default().push(row);
+    }
+
+    let mut added = BTreeMap::<Key, Vec<&Row>>::new();
+    for row in after.difference(before) {
+      added.entry(key(row)).or_default().push(row);
+    }
+
+    let mut diff = Self::default();
+
+    for (key, mut after) in added {
+      after.sort_by_key(|row| row.index);
+
+      let mut before = removed.remove(&key).unwrap_or_default();
+      before.sort_by_key(|row| row.index);
+
+      let mut before = before.into_iter();
+      for after in after {
+        match before.next() {
+          Some(before) => diff.modified.push(Modification {
+            before: before.clone(),
+            after: after.clone(),
+          }),
+          None => diff.added.push(after.clone()),
+        }
+      }
+
+      diff.removed.extend(before.cloned());
+    }
+
+    for (_, before) in removed {
+      diff.removed.extend(before.into_iter().cloned());
+    }
+
+    diff.added.sort_by_key(|row| (row.date, row.index));
+    diff.removed.sort_by_key(|row| (row.date, row.index));
+    diff
+      .modified
+      .sort_by_key(|modification| (modification.after.date, modification.after.index));
+
+    diff
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+  }
+
+  /// Keeps only rows matching `predicate`, modifications are kept if either side matches.
+  pub fn filter(&self, predicate: impl Fn(&Row) -> bool) -> Self {
+    Self {
+      added: self
+        .added
+        .iter()
+        .filter(|row| predicate(row))
+        .cloned()
+        .collect(),
+      removed: self
+        .removed
+        .iter()
+        .filter(|row| predicate(row))
+        .cloned()
+        .collect(),
+      modified: self
+        .modified
+        .iter()
+        .filter(|modification| predicate(&modification.before) || predicate(&modification.after))
+        .cloned()
+        .collect(),
+    }
+  }
+
+  /// Lists each move once, the plan has a row at both of its dates.
+  pub fn merge_moves(&self) -> Self {
+    Self {
+      added: distinct(&self.added, |row| row),
+      removed: distinct(&self.removed, |row| row),
+      modified: distinct(&self.modified, |modification| &modification.after),
+    }
+  }
+}
+
+/// The items in order, leaving out the rows of moves already listed at their other date.
+pub(crate) fn distinct<T: Clone>(items: &[T], row: impl Fn(&T) -> &Row) -> Vec<T> {
+  items
+    .iter()
+    .enumerate()
+    .filter(|(index, item)| {
+      !items[..*index].iter().any(|other| {
+        same_classes(&row(other).class, &row(item).class)
+          && row(other).change.same_move(&row(item).change)
+      })
+    })
+    .map(|(_, item)| item.clone())
+    .collect()
+}
+
+/// The rows of a move may spell the class differently, like `IGD21` and `IGD 21`.
+pub(crate) fn same_classes(a: &[String], b: &[String]) -> bool {
+  let compact = |classes: &[String]| {
+    classes
+      .iter()
+      .map(|class| class.replace(' ', ""))
+      .collect::<Vec<String>>()
+  };
+
+  compact(a) == compact(b)
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashSet;
+
+  use time::{Date, Month};
+
+  use crate::change::Change;
+  use crate::diff::Diff;
+  use crate::Row;
+
+  fn row(index: u8, lesson: u8, value: &str, subject: &str, place: &str) -> Row {
+    Row {
+      index,
+      date: Date::from_calendar_date(2023, Month::September, 25).unwrap(),
+      class: vec!["IGD21".to_string()],
+      change: Change::new(lesson, value, subject, place.to_string(), "Mül", None).unwrap(),
+      raw: vec![],
+    }
+  }
+
+  #[test]
+  fn test_between() {
+    let unchanged = row(0, 1, "Fällt aus", "DEU", "B6");
+    let removed = row(1, 2, "Fällt aus", "CH", "B9");
+    let before = row(2, 3, "Raumänderung", "LF11D", "+B9 (B5)");
+    let after = row(2, 3, "Raumänderung", "LF11D", "+B8 (B5)");
+    let added = row(3, 4, "Fällt aus", "GGK", "B4");
+
+    let diff = Diff::between(
+      &HashSet::from([unchanged.clone(), removed.clone(), before.clone()]),
+      &HashSet::from([unchanged, after.clone(), added.clone()]),
+    );
+
+    assert_eq!(vec![added], diff.added);
+    assert_eq!(vec![removed], diff.removed);
+    assert_eq!(1, diff.modified.len());
+    assert_eq!(before, diff.modified[0].before);
+    assert_eq!(after, diff.modified[0].after);
+
+    assert!(Diff::between(&HashSet::new(), &HashSet::new()).is_empty());
+  }
+
+  #[test]
+  fn test_filter() {
+    let mut other = row(0, 1, "Fällt aus", "DEU", "B6");
+    other.class = vec!["IGD22".to_string()];
+
+    let diff = Diff::between(
+      &HashSet::new(),
+      &HashSet::from([other, row(1, 2, "Fällt aus", "CH", "B9")]),
+    );
+    let filtered = diff.filter(|row| row.class.contains(&"IGD21".to_string()));
+
+    assert_eq!(2, diff.added.len());
+    assert_eq!(1, filtered.added.len());
+  }
+}
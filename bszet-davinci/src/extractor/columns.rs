@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use tracing::debug;
+
+/// Columns of the DaVinci export, identified by their `<th>` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Column {
+  Class,
+  Lesson,
+  Subject,
+  Place,
+  Teacher,
+  Kind,
+  Notice,
+  /// Teacher of the timetable, `(Lehrkraft)`
+  OriginalTeacher,
+  /// Absent teacher, `Vertretung von`
+  ReplacedTeacher,
+}
+
+/// Layout of the raw rows, also used by the html template and image renderers.
+pub(crate) const RAW_COLUMNS: [Column; 7] = [
+  Column::Class,
+  Column::Lesson,
+  Column::Subject,
+  Column::Place,
+  Column::Teacher,
+  Column::Kind,
+  Column::Notice,
+];
+
+const REQUIRED: [Column; 4] = [Column::Class, Column::Lesson, Column::Subject, Column::Kind];
+
+impl Column {
+  fn from_header(header: &str) -> Option<Self> {
+    // exports pad headers with non-breaking spaces and line breaks
+    let header = header.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let header = header.trim_end_matches('.').to_lowercase();
+
+    Some(match header.as_str() {
+      "klasse" | "klassen" => Self::Class,
+      "stunde" | "std" => Self::Lesson,
+      "fach" => Self::Subject,
+      "raum" => Self::Place,
+      "lehrkraft" | "lehrer" => Self::Teacher,
+      "art" => Self::Kind,
+      "mitteilung" | "bemerkung" | "text" => Self::Notice,
+      "(lehrkraft)" | "(lehrer)" => Self::OriginalTeacher,
+      "vertretung von" => Self::ReplacedTeacher,
+      _ => return None,
+    })
+  }
+}
+
+/// Positions of the known columns in a table.
+#[derive(Debug)]
+pub(crate) struct Columns {
+  positions: HashMap<Column, usize>,
+  len: usize,
+}
+
+impl Default for Columns {
+  /// Layout of exports without header row.
+  fn default() -> Self {
+    Self {
+      positions: RAW_COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(position, column)| (*column, position))
+        .collect(),
+      len: RAW_COLUMNS.len(),
+    }
+  }
+}
+
+impl Columns {
+  /// Maps the header row, unknown columns are ignored.
+  pub(crate) fn from_header(header: &[String]) -> anyhow::Result<Self> {
+    if header.is_empty() {
+      return Ok(Self::default());
+    }
+
+    let mut positions = HashMap::new();
+
+    for (position, name) in header.iter().enumerate() {
+      match Column::from_header(name) {
+        None => debug!("Ignoring unknown column {}", name),
+        Some(column) => {
+          if positions.insert(column, position).is_some() {
+            return Err(anyhow!("Duplicate column {name}"));
+          }
+        }
+      }
+    }
+
+    for column in REQUIRED {
+      if !positions.contains_key(&column) {
+        return Err(anyhow!("Missing column {column:?}"));
+      }
+    }
+
+    Ok(Self {
+      positions,
+      len: header.len(),
+    })
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Value of `column` in `row`, empty if the table has no such column.
+  pub(crate) fn get<'a>(&self, column: Column, row: &'a [String]) -> &'a str {
+    self
+      .positions
+      .get(&column)
+      .and_then(|position| row.get(*position))
+      .map(String::as_str)
+      .unwrap_or_default()
+  }
+
+  /// Values of `row` in the order of [`RAW_COLUMNS`].
+  pub(crate) fn raw(&self, row: &[String]) -> Vec<String> {
+    RAW_COLUMNS
+      .iter()
+      .map(|column| self.get(*column, row).to_string())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::extractor::columns::{Column, Columns};
+
+  fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+  }
+
+  #[test]
+  fn test_columns() -> anyhow::Result<()> {
+    let columns = Columns::from_header(&strings(&[
+      "Std.",
+      "Klasse",
+      "Art",
+      "Fach",
+      "Vertretung von",
+      "Lehrkraft",
+      "Raum",
+      "Stand",
+    ]))?;
+    let row = strings(&[
+      "3.",
+      "IGD21",
+      "Vertreten",
+      "MA",
+      "Kol",
+      "Sch",
+      "B11",
+      "heute",
+    ]);
+
+    assert_eq!(8, columns.len());
+    assert_eq!("IGD21", columns.get(Column::Class, &row));
+    assert_eq!("3.", columns.get(Column::Lesson, &row));
+    assert_eq!("Kol", columns.get(Column::ReplacedTeacher, &row));
+    assert_eq!("", columns.get(Column::Notice, &row));
+    assert_eq!(
+      strings(&["IGD21", "3.", "MA", "B11", "Sch", "Vertreten", ""]),
+      columns.raw(&row)
+    );
+
+    let columns = Columns::from_header(&strings(&[
+      "Std.\u{a0}",
+      "Klasse",
+      "Fach",
+      "Art",
+      "Vertretung\u{a0}von",
+      "\n      (Lehrkraft)\n    ",
+      "",
+    ]))?;
+    assert_eq!(
+      "Kol",
+      columns.get(
+        Column::ReplacedTeacher,
+        &strings(&["3.", "IGD21", "MA", "", "Kol"])
+      )
+    );
+    assert_eq!(
+      "Sch",
+      columns.get(
+        Column::OriginalTeacher,
+        &strings(&["3.", "IGD21", "MA", "", "", "Sch"])
+      )
+    );
+
+    let columns = Columns::from_header(&[])?;
+    assert_eq!(7, columns.len());
+    assert_eq!(
+      "MA",
+      columns.get(Column::Subject, &strings(&["IGD21", "3.", "MA"]))
+    );
+
+    assert!(Columns::from_header(&strings(&["Klasse", "Stunde", "Fach", "Raum"])).is_err());
+    assert!(Columns::from_header(&strings(&["Klasse", "Std.", "Stunde", "Fach", "Art"])).is_err());
+
+    Ok(())
+  }
+}
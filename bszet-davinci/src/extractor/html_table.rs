@@ -3,10 +3,24 @@ use select::predicate::Name;
 
 use crate::extractor::clean;
 
-pub(crate) fn extract_html_table(doc: &Document) -> Vec<Vec<String>> {
+pub(crate) struct HtmlTable {
+  /// Text of the `<th>` cells, empty if the table has no header row
+  pub(crate) header: Vec<String>,
+  pub(crate) rows: Vec<Vec<String>>,
+}
+
+pub(crate) fn extract_html_table(doc: &Document) -> HtmlTable {
+  let mut header = Vec::new();
   let mut rows = Vec::new();
 
   for row in doc.find(Name("tr")) {
+    if header.is_empty() && rows.is_empty() {
+      header = row
+        .find(Name("th"))
+        .map(|head| head.text().trim().to_string())
+        .collect();
+    }
+
     let mut columns = Vec::new();
 
     for data in row.find(Name("td")) {
@@ -18,5 +32,5 @@ pub(crate) fn extract_html_table(doc: &Document) -> Vec<Vec<String>> {
     }
   }
 
-  rows
+  HtmlTable { header, rows }
 }
@@ -10,6 +10,7 @@ use time::{Date, Month};
 pub(crate) use html_table::*;
 pub(crate) use parser::*;
 
+mod columns;
 mod html_table;
 mod parser;
 
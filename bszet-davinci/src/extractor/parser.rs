@@ -1,74 +1,126 @@
-use std::str::FromStr;
-
 use anyhow::anyhow;
 use time::Date;
 
-use crate::change::Change;
-use crate::Row;
+use crate::change::{Change, Slot};
+use crate::extractor::columns::{Column, Columns};
+use crate::extractor::HtmlTable;
+use crate::kinds::{ChangeKinds, Kind};
+use crate::lessons::{LessonMode, Lessons};
+use crate::subjects::SubjectDictionary;
+use crate::{Row, REPLACEMENT_REGEX};
 
+/// Parses the rows of a page into `rows`, a day may continue from the previous page whose last
+/// row is `previous`. Returns whether the first row took its class or lessons from it.
 pub(crate) fn parse(
-  table: Vec<Vec<String>>,
+  table: HtmlTable,
   date: &Date,
+  mode: LessonMode,
+  kinds: &ChangeKinds,
+  subjects: &SubjectDictionary,
+  previous: Option<&Row>,
   rows: &mut Vec<Row>,
-) -> anyhow::Result<()> {
-  for (index, columns) in table.into_iter().enumerate() {
-    if columns.len() != 7 {
+) -> anyhow::Result<bool> {
+  let layout = Columns::from_header(&table.header)?;
+  let previous = previous.filter(|row| row.date == *date);
+  let mut continued = false;
+
+  for (index, row) in table.rows.into_iter().enumerate() {
+    if row.len() != layout.len() {
       return Err(anyhow!(
-        "Invalid amount of columns; expected 7, got {}",
-        columns.len()
+        "Invalid amount of columns; expected {}, got {}",
+        layout.len(),
+        row.len()
       ));
     }
 
-    let class = parse_optional(&columns[0]).map(parse_list);
+    let class = parse_optional(layout.get(Column::Class, &row)).map(parse_list);
 
-    let lesson = match parse_optional(&columns[1]) {
-      Some(lesson) => Some(parse_lesson(lesson)?),
+    let lessons = match parse_optional(layout.get(Column::Lesson, &row)) {
+      Some(lessons) => Some(Lessons::parse(lessons, mode)?),
       None => None,
     };
 
-    let notice = parse_optional(&columns[6]).map(|notice| notice.to_string());
-
-    let type_of_change = &columns[5];
-    let subject = &columns[2];
-    let place = columns[3].to_string();
-    let teachers = &columns[4];
-
-    let row = if let Some(last) = rows.last() {
-      Row {
-        index: index as u8,
-        date: *date,
-        class: class.unwrap_or_else(|| last.class.clone()),
-        change: Change::new(
-          lesson.unwrap_or_else(|| last.change.lesson()),
-          type_of_change,
-          subject,
-          place,
-          teachers,
-          notice,
-        )?,
-        raw: columns,
-      }
-    } else {
-      Row {
-        index: index as u8,
-        date: *date,
-        class: class.ok_or_else(|| anyhow!("First row, can not have missing fields."))?,
-        change: Change::new(
-          lesson.ok_or_else(|| anyhow!("First row, can not have missing fields."))?,
-          type_of_change,
-          subject,
-          place,
-          teachers,
-          notice,
-        )?,
-        raw: columns,
-      }
+    let notice = parse_optional(layout.get(Column::Notice, &row)).map(|notice| notice.to_string());
+
+    let type_of_change = layout.get(Column::Kind, &row);
+    let subject = layout.get(Column::Subject, &row);
+    let place = layout.get(Column::Place, &row).to_string();
+    let replacement = kinds.get(type_of_change) == Some(Kind::Replacement);
+    let teachers = &teachers(&layout, &row, replacement);
+    let columns = layout.raw(&row);
+
+    if rows.is_empty() && (class.is_none() || lessons.is_none()) {
+      continued = previous.is_some();
+    }
+
+    let (class, lessons) = match rows.last().or(previous) {
+      Some(last) => (
+        class.unwrap_or_else(|| last.class.clone()),
+        lessons.unwrap_or_else(|| last.change.lessons()),
+      ),
+      None => (
+        class.ok_or_else(|| anyhow!("First row, can not have missing fields."))?,
+        lessons.ok_or_else(|| anyhow!("First row, can not have missing fields."))?,
+      ),
+    };
+
+    let here = Slot {
+      date: *date,
+      lessons,
+    };
+    let change = match Change::moved(
+      subjects,
+      here,
+      mode,
+      type_of_change,
+      subject,
+      &place,
+      teachers,
+      notice.clone(),
+    ) {
+      Some(change) => change,
+      None => Change::with_kinds(
+        kinds,
+        subjects,
+        lessons,
+        type_of_change,
+        subject,
+        place,
+        teachers,
+        notice,
+      )?,
+    };
+
+    let row = Row {
+      index: index as u8,
+      date: *date,
+      class,
+      change,
+      raw: columns,
     };
 
     rows.push(row);
   }
 
-  Ok(())
+  Ok(continued)
+}
+
+/// Teachers in the notation of the `Lehrkraft` column, some exports list the
+/// absent teacher of a replacement in a separate column.
+fn teachers(layout: &Columns, row: &[String], replacement: bool) -> String {
+  let teacher = layout.get(Column::Teacher, row);
+
+  let absent = [Column::ReplacedTeacher, Column::OriginalTeacher]
+    .into_iter()
+    .map(|column| layout.get(column, row))
+    .find(|absent| !absent.is_empty());
+
+  match absent {
+    Some(absent) if replacement && absent != teacher && !REPLACEMENT_REGEX.is_match(teacher) => {
+      format!("+{teacher} ({absent})")
+    }
+    _ => teacher.to_string(),
+  }
 }
 
 fn parse_optional(value: &str) -> Option<&str> {
@@ -85,35 +137,3 @@ fn parse_list(value: &str) -> Vec<String> {
     .map(|string| string.trim().to_string())
     .collect()
 }
-
-fn parse_lesson(value: &str) -> anyhow::Result<u8> {
-  // split ending point from number
-  let raw = &value[..value.len() - 1];
-  let num = u8::from_str(raw)?;
-
-  Ok(convert_lesson(num))
-}
-
-/// Convert raw lesson to block lesson
-fn convert_lesson(lesson: u8) -> u8 {
-  (lesson + lesson % 2) / 2
-}
-
-#[cfg(test)]
-mod test {
-  use crate::extractor::parser::convert_lesson;
-
-  #[test]
-  fn test_convert_lesson() {
-    assert_eq!(1, convert_lesson(1));
-    assert_eq!(1, convert_lesson(2));
-    assert_eq!(2, convert_lesson(3));
-    assert_eq!(2, convert_lesson(4));
-    assert_eq!(3, convert_lesson(5));
-    assert_eq!(3, convert_lesson(6));
-    assert_eq!(4, convert_lesson(7));
-    assert_eq!(4, convert_lesson(8));
-    assert_eq!(5, convert_lesson(9));
-    assert_eq!(5, convert_lesson(10));
-  }
-}
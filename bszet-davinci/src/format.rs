@@ -0,0 +1,45 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use time::serde::format_description;
+
+format_description!(pub(crate) iso_date, Date, "[year]-[month]-[day]");
+
+/// Supported formats of data files.
+pub(crate) enum Format {
+  Toml,
+  Json,
+  Yaml,
+}
+
+impl Format {
+  pub(crate) fn from_path(path: &Path) -> Option<Self> {
+    match path.extension()?.to_str()? {
+      "toml" => Some(Self::Toml),
+      "json" => Some(Self::Json),
+      "yaml" | "yml" => Some(Self::Yaml),
+      _ => None,
+    }
+  }
+
+  pub(crate) fn parse<T: DeserializeOwned>(&self, content: &str) -> anyhow::Result<T> {
+    Ok(match self {
+      Self::Toml => toml::from_str(content)?,
+      Self::Json => serde_json::from_str(content)?,
+      Self::Yaml => serde_yaml::from_str(content)?,
+    })
+  }
+}
+
+/// Reads and deserializes the file at `path`, the format is derived from the file extension.
+pub fn load<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+  let format =
+    Format::from_path(path).ok_or_else(|| anyhow!("Unsupported file type {}", path.display()))?;
+  let content = read_to_string(path)?;
+
+  format
+    .parse(&content)
+    .map_err(|err| anyhow!("Unable to parse {}: {}", path.display(), err))
+}
@@ -1,6 +1,11 @@
+use std::ops::RangeInclusive;
+
 use sailfish::TemplateOnce;
 use time::Date;
 
+use crate::timetable::{Lesson, Subject};
+use crate::AppliedDay;
+
 #[derive(TemplateOnce)]
 #[template(path = "plan.stpl", rm_whitespace = true)]
 pub(crate) struct SubstitutionPlanTemplate<'a> {
@@ -9,6 +14,31 @@ pub(crate) struct SubstitutionPlanTemplate<'a> {
   pub(crate) classes: &'a [&'a str],
 }
 
+#[derive(TemplateOnce)]
+#[template(path = "week.stpl", rm_whitespace = true)]
+pub(crate) struct WeekTemplate<'a> {
+  pub(crate) class: &'a str,
+  pub(crate) monday: Date,
+  pub(crate) days: &'a [AppliedDay],
+}
+
+/// Rows of the week grid, up to the last lesson of the week.
+fn lesson_numbers(days: &[AppliedDay]) -> RangeInclusive<u8> {
+  let last = days
+    .iter()
+    .flat_map(|day| &day.lessons)
+    .map(|lesson| lesson.lesson)
+    .max()
+    .unwrap_or(0);
+
+  1..=last
+}
+
+/// Lessons are changed if they are cancelled or a change left a notice.
+fn is_changed(lesson: &Lesson) -> bool {
+  matches!(lesson.subject, Subject::Cancel(_)) || lesson.notice.is_some()
+}
+
 #[cfg(test)]
 mod test {
   use sailfish::TemplateOnce;
@@ -49,7 +79,7 @@ mod test {
 
     let table = vec![a.as_slice(), b.as_slice(), c.as_slice(), a.as_slice()];
 
-    let classes = vec!["IGD 21".to_string(), "IGD21".to_string()];
+    let classes = vec!["IGD 21", "IGD21"];
 
     let template = SubstitutionPlanTemplate {
       date: Date::from_calendar_date(2023, January, 28)?,
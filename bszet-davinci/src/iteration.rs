@@ -1,345 +1,170 @@
-use std::collections::HashMap;
-
-use time::{Date, Duration, Month};
-
-pub(crate) fn get_iteration(date: Date) -> Option<u8> {
-  let iterations = HashMap::from([
-    (
-      Date::from_calendar_date(2021, Month::September, 6).unwrap(),
-      1u8,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::September, 13).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::September, 20).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::September, 27).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::October, 4).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::October, 11).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::November, 1).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::November, 8).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::November, 15).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::November, 22).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::November, 29).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::December, 6).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::December, 13).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2021, Month::December, 20).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::January, 3).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::January, 10).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::January, 17).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::January, 24).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::January, 31).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::February, 7).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::February, 28).unwrap(),
-      1,
-    ),
-    (Date::from_calendar_date(2022, Month::March, 7).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::March, 14).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::March, 21).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::March, 28).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::April, 4).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::April, 11).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::April, 25).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::May, 2).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::May, 9).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::May, 16).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::May, 23).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::May, 30).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::June, 7).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::June, 13).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::June, 20).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::June, 27).unwrap(), 1),
-    (Date::from_calendar_date(2022, Month::July, 4).unwrap(), 2),
-    (Date::from_calendar_date(2022, Month::July, 11).unwrap(), 1),
-    // SUMMER HOLIDAYS 2022 🌴
-    (
-      Date::from_calendar_date(2022, Month::August, 29).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::September, 5).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::September, 12).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::September, 19).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::September, 26).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::October, 3).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::October, 10).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::October, 31).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::November, 7).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::November, 14).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::November, 21).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::November, 28).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::December, 5).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::December, 12).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2022, Month::December, 19).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::January, 2).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::January, 9).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::January, 16).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::January, 23).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::January, 30).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::February, 6).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::February, 20).unwrap(),
-      2,
-    ),
-    (Date::from_calendar_date(2023, Month::March, 6).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::March, 13).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::March, 20).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::March, 27).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::April, 3).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::April, 17).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::April, 24).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::May, 1).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::May, 8).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::May, 15).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::May, 22).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::May, 29).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::June, 5).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::June, 12).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::June, 19).unwrap(), 1),
-    (Date::from_calendar_date(2023, Month::June, 26).unwrap(), 2),
-    (Date::from_calendar_date(2023, Month::July, 3).unwrap(), 1),
-    // SUMMER HOLIDAYS 2023 🌴
-    (
-      Date::from_calendar_date(2023, Month::August, 21).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::August, 28).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::September, 4).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::September, 11).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::September, 18).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::September, 25).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::October, 16).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::October, 23).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::October, 30).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::November, 6).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::November, 13).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::November, 20).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::November, 27).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::December, 4).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::December, 11).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2023, Month::December, 18).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::January, 1).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::January, 8).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::January, 15).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::January, 22).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::January, 29).unwrap(),
-      1,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::February, 5).unwrap(),
-      2,
-    ),
-    (
-      Date::from_calendar_date(2024, Month::February, 26).unwrap(),
-      1,
-    ),
-    (Date::from_calendar_date(2024, Month::March, 4).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::March, 11).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::March, 18).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::March, 25).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::April, 8).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::April, 15).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::April, 22).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::April, 29).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::May, 6).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::May, 13).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::May, 20).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::May, 27).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::June, 3).unwrap(), 2),
-    (Date::from_calendar_date(2024, Month::June, 10).unwrap(), 1),
-    (Date::from_calendar_date(2024, Month::June, 17).unwrap(), 2),
-  ]);
-
-  for i in 0..7i64 {
-    let date = date - Duration::days(i);
-    if let Some(date) = iterations.get(&date) {
-      return Some(*date);
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use time::{Date, Duration};
+
+use crate::format::{iso_date, load};
+
+/// Calendar of the alternating A/B weeks (Turnus) of a school year.
+///
+/// Starting with `iteration` in the week of `start`, every school week flips
+/// between iteration 1 and 2. Weeks which are completely covered by holidays
+/// do not count as school weeks and are skipped.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IterationCalendar {
+  #[serde(with = "iso_date")]
+  start: Date,
+  #[serde(default, with = "iso_date::option")]
+  end: Option<Date>,
+  iteration: u8,
+  #[serde(default)]
+  holidays: Vec<Holiday>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Holiday {
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(with = "iso_date")]
+  pub from: Date,
+  #[serde(with = "iso_date")]
+  pub to: Date,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Week {
+  pub monday: Date,
+  /// `None` for holiday weeks
+  pub iteration: Option<u8>,
+}
+
+impl IterationCalendar {
+  pub fn new(start: Date, end: Option<Date>, iteration: u8, holidays: Vec<Holiday>) -> Self {
+    Self {
+      start,
+      end,
+      iteration,
+      holidays,
     }
   }
 
-  None
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    let calendar = load::<Self>(path)?;
+    calendar
+      .validate()
+      .map_err(|err| anyhow!("Invalid calendar {}: {}", path.display(), err))?;
+    Ok(calendar)
+  }
+
+  fn validate(&self) -> anyhow::Result<()> {
+    if self.iteration != 1 && self.iteration != 2 {
+      return Err(anyhow!("iteration is {}, expected 1 or 2", self.iteration));
+    }
+
+    if let Some(end) = self.end {
+      if end < self.start {
+        return Err(anyhow!("end {} is before start {}", end, self.start));
+      }
+    }
+
+    for holiday in &self.holidays {
+      if holiday.to < holiday.from {
+        return Err(anyhow!(
+          "holiday {} ends before it starts",
+          holiday.name.as_deref().unwrap_or("")
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Returns the iteration of the week `date` is in, `None` if the date is
+  /// outside of the school year or within a holiday week.
+  pub fn get_iteration(&self, date: Date) -> Option<u8> {
+    if self.has_ended(date) {
+      return None;
+    }
+
+    let start = monday(self.start);
+    let target = monday(date);
+    if target < start || self.is_holiday_week(target) {
+      return None;
+    }
+
+    let mut school_weeks = 0;
+    let mut week = start;
+    while week < target {
+      if !self.is_holiday_week(week) {
+        school_weeks += 1;
+      }
+      week += Duration::weeks(1);
+    }
+
+    Some(if school_weeks % 2 == 0 {
+      self.iteration
+    } else {
+      3 - self.iteration
+    })
+  }
+
+  /// Lists the next `count` weeks starting with the week of `from`.
+  pub fn weeks(&self, from: Date, count: usize) -> Vec<Week> {
+    let mut weeks = Vec::with_capacity(count);
+    let mut week = monday(from);
+
+    for _ in 0..count {
+      weeks.push(Week {
+        monday: week,
+        iteration: self.get_iteration(week),
+      });
+      week += Duration::weeks(1);
+    }
+
+    weeks
+  }
+
+  /// Whether the school year of the calendar is over at `date`.
+  pub fn has_ended(&self, date: Date) -> bool {
+    self.end.is_some_and(|end| date > end)
+  }
+
+  /// A week is a holiday week if all days from monday to friday are holidays.
+  fn is_holiday_week(&self, monday: Date) -> bool {
+    (0..5).all(|day| self.is_holiday(monday + Duration::days(day)))
+  }
+
+  pub fn is_holiday(&self, date: Date) -> bool {
+    self
+      .holidays
+      .iter()
+      .any(|holiday| holiday.from <= date && date <= holiday.to)
+  }
+}
+
+fn monday(date: Date) -> Date {
+  date - Duration::days(date.weekday().number_days_from_monday() as i64)
 }
 
 #[cfg(test)]
 mod test {
-  use time::{Date, Month};
+  use std::path::Path;
 
-  use crate::iteration::get_iteration;
+  use time::{Date, Month, Weekday};
+
+  use crate::iteration::{Holiday, IterationCalendar, Week};
+
+  fn date(year: i32, month: Month, day: u8) -> Date {
+    Date::from_calendar_date(year, month, day).unwrap()
+  }
 
   #[test]
   fn test_get_iteration() {
+    let calendar = IterationCalendar::new(date(2021, Month::September, 6), None, 1, vec![]);
+
     let date1 = Date::from_calendar_date(2021, Month::September, 13).unwrap();
     let date2 = Date::from_calendar_date(2021, Month::September, 14).unwrap();
     let date3 = Date::from_calendar_date(2021, Month::September, 15).unwrap();
@@ -350,14 +175,101 @@ mod test {
     let date8 = Date::from_calendar_date(2021, Month::September, 20).unwrap();
     let date9 = Date::from_calendar_date(2021, Month::September, 21).unwrap();
 
-    assert_eq!(Some(2), get_iteration(date1));
-    assert_eq!(Some(2), get_iteration(date2));
-    assert_eq!(Some(2), get_iteration(date3));
-    assert_eq!(Some(2), get_iteration(date4));
-    assert_eq!(Some(2), get_iteration(date5));
-    assert_eq!(Some(2), get_iteration(date6));
-    assert_eq!(Some(2), get_iteration(date7));
-    assert_eq!(Some(1), get_iteration(date8));
-    assert_eq!(Some(1), get_iteration(date9));
+    assert_eq!(Some(2), calendar.get_iteration(date1));
+    assert_eq!(Some(2), calendar.get_iteration(date2));
+    assert_eq!(Some(2), calendar.get_iteration(date3));
+    assert_eq!(Some(2), calendar.get_iteration(date4));
+    assert_eq!(Some(2), calendar.get_iteration(date5));
+    assert_eq!(Some(2), calendar.get_iteration(date6));
+    assert_eq!(Some(2), calendar.get_iteration(date7));
+    assert_eq!(Some(1), calendar.get_iteration(date8));
+    assert_eq!(Some(1), calendar.get_iteration(date9));
+  }
+
+  #[test]
+  fn test_holidays() {
+    let calendar = IterationCalendar::new(
+      date(2023, Month::August, 21),
+      Some(date(2024, Month::June, 19)),
+      1,
+      vec![
+        Holiday {
+          name: Some("Herbstferien".to_string()),
+          from: date(2023, Month::October, 2),
+          to: date(2023, Month::October, 14),
+        },
+        Holiday {
+          name: Some("Weihnachtsferien".to_string()),
+          from: date(2023, Month::December, 23),
+          to: date(2024, Month::January, 2),
+        },
+      ],
+    );
+
+    assert_eq!(None, calendar.get_iteration(date(2023, Month::August, 18)));
+    assert_eq!(
+      Some(2),
+      calendar.get_iteration(date(2023, Month::September, 25))
+    );
+    assert_eq!(None, calendar.get_iteration(date(2023, Month::October, 4)));
+    assert_eq!(
+      Some(1),
+      calendar.get_iteration(date(2023, Month::October, 16))
+    );
+    assert_eq!(
+      Some(2),
+      calendar.get_iteration(date(2023, Month::December, 18))
+    );
+    assert_eq!(
+      None,
+      calendar.get_iteration(date(2023, Month::December, 27))
+    );
+    // partial holiday week
+    assert_eq!(
+      Some(1),
+      calendar.get_iteration(date(2024, Month::January, 1))
+    );
+    assert_eq!(None, calendar.get_iteration(date(2024, Month::June, 20)));
+
+    assert_eq!(
+      vec![
+        Week {
+          monday: date(2023, Month::September, 25),
+          iteration: Some(2)
+        },
+        Week {
+          monday: date(2023, Month::October, 2),
+          iteration: None
+        },
+        Week {
+          monday: date(2023, Month::October, 9),
+          iteration: None
+        },
+        Week {
+          monday: date(2023, Month::October, 16),
+          iteration: Some(1)
+        },
+      ],
+      calendar.weeks(date(2023, Month::September, 27), 4)
+    );
+  }
+
+  #[test]
+  fn test_load_shipped_calendar() -> anyhow::Result<()> {
+    let calendar =
+      IterationCalendar::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../calendar.toml"))?;
+
+    // the dates themselves have to be checked against the published holidays
+    let end = calendar.end.expect("school year without end");
+    assert_eq!(Weekday::Monday, calendar.start.weekday());
+    assert_eq!(Weekday::Friday, end.weekday());
+    for holiday in &calendar.holidays {
+      assert!(
+        calendar.start <= holiday.from && holiday.to <= end,
+        "{holiday:?} is outside of the school year"
+      );
+    }
+
+    Ok(())
   }
 }
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::format;
+
+static MOVED_FROM_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("Von .+ verschoben").unwrap());
+static MOVED_TO_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("Auf .+ verschoben").unwrap());
+
+/// How a type of change, the `Art` column of the plan, is applied to the timetable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+  Cancel,
+  PlaceChange,
+  Addition,
+  Replacement,
+  /// The lesson is an exam, possibly in another place
+  Exam,
+  /// The place of the lesson is unavailable
+  RoomBlocked,
+  /// The teacher is absent, substitution is not decided yet
+  TeacherAbsent,
+  /// Another teacher supervises the lesson
+  Supervision,
+  /// The lesson takes place without teacher
+  SelfStudy,
+}
+
+/// Maps the types of change of the plan to their [`Kind`], unknown types are kept
+/// as `Other` and can not be applied.
+#[derive(Clone, Debug)]
+pub struct ChangeKinds {
+  kinds: HashMap<String, Kind>,
+}
+
+#[derive(Deserialize)]
+struct ChangeKindsFile {
+  kinds: HashMap<String, Kind>,
+}
+
+impl Default for ChangeKinds {
+  /// The types of change known before the mapping file existed.
+  fn default() -> Self {
+    Self::from_iter([
+      ("Fällt aus", Kind::Cancel),
+      ("Klasse fehlt", Kind::Cancel),
+      ("Raumänderung", Kind::PlaceChange),
+      ("Zusatzunterricht", Kind::Addition),
+      ("Vertreten", Kind::Replacement),
+    ])
+  }
+}
+
+impl<'a> FromIterator<(&'a str, Kind)> for ChangeKinds {
+  fn from_iter<T: IntoIterator<Item = (&'a str, Kind)>>(iter: T) -> Self {
+    Self {
+      kinds: iter
+        .into_iter()
+        .map(|(value, kind)| (normalize(value), kind))
+        .collect(),
+    }
+  }
+}
+
+impl ChangeKinds {
+  /// Loads a mapping like `"Klausur" = "exam"` below `[kinds]`, the entries extend
+  /// and override the defaults.
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    let file = format::load::<ChangeKindsFile>(path)?;
+
+    let mut kinds = Self::default();
+    kinds.kinds.extend(
+      file
+        .kinds
+        .into_iter()
+        .map(|(value, kind)| (normalize(&value), kind)),
+    );
+
+    Ok(kinds)
+  }
+
+  /// Kind of the type of change, case is ignored. Moves which could not be
+  /// parsed are cancelled at their origin and replace the lesson at their destination.
+  pub fn get(&self, value: &str) -> Option<Kind> {
+    self.kinds.get(&normalize(value)).copied().or_else(|| {
+      if MOVED_TO_REGEX.is_match(value) {
+        Some(Kind::Cancel)
+      } else if MOVED_FROM_REGEX.is_match(value) {
+        Some(Kind::Replacement)
+      } else {
+        None
+      }
+    })
+  }
+}
+
+fn normalize(value: &str) -> String {
+  value.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::Path;
+
+  use crate::kinds::{ChangeKinds, Kind};
+
+  #[test]
+  fn test_kinds() -> anyhow::Result<()> {
+    let defaults = ChangeKinds::default();
+    assert_eq!(Some(Kind::Cancel), defaults.get("Fällt aus"));
+    assert_eq!(Some(Kind::Replacement), defaults.get(" vertreten "));
+    assert_eq!(
+      Some(Kind::Cancel),
+      defaults.get("Auf Mi, 06.09. verschoben")
+    );
+    assert_eq!(None, defaults.get("Klausur"));
+
+    let kinds = ChangeKinds::load(
+      &Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("change-kinds.toml"),
+    )?;
+    assert_eq!(Some(Kind::Exam), kinds.get("Klausur"));
+    assert_eq!(Some(Kind::RoomBlocked), kinds.get("Raum gesperrt"));
+    assert_eq!(Some(Kind::TeacherAbsent), kinds.get("Lehrkraft fehlt"));
+    assert_eq!(Some(Kind::Supervision), kinds.get("Aufsicht"));
+    assert_eq!(Some(Kind::SelfStudy), kinds.get("Selbststudium"));
+    assert_eq!(Some(Kind::Addition), kinds.get("Zusatzunterricht"));
+
+    Ok(())
+  }
+}
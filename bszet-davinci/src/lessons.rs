@@ -0,0 +1,181 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+static LESSONS_REGEX: Lazy<Regex> =
+  Lazy::new(|| Regex::new("^(\\d+)\\.?(?:\\s*-\\s*(\\d+)\\.?)?$").unwrap());
+
+/// How a school counts its lessons.
+#[derive(
+  Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LessonMode {
+  /// Two periods form a block, the plan lists periods, timetables blocks
+  #[default]
+  Blocks,
+  /// Plan and timetables list single periods
+  Periods,
+}
+
+/// Consecutive lessons affected by a change, in units of the [`LessonMode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Lessons {
+  pub from: u8,
+  pub to: u8,
+  pub mode: LessonMode,
+}
+
+impl Lessons {
+  pub fn iter(&self) -> RangeInclusive<u8> {
+    self.from..=self.to
+  }
+
+  pub fn contains(&self, lesson: u8) -> bool {
+    self.iter().contains(&lesson)
+  }
+
+  /// Parses the lesson column, like `3.`, `3.-4.` or `1 - 4`.
+  pub(crate) fn parse(value: &str, mode: LessonMode) -> anyhow::Result<Self> {
+    let captures = LESSONS_REGEX
+      .captures(value.trim())
+      .ok_or_else(|| anyhow!("Invalid lesson {value}"))?;
+
+    let from = u8::from_str(captures.get(1).unwrap().as_str())?;
+    let to = match captures.get(2) {
+      Some(to) => u8::from_str(to.as_str())?,
+      None => from,
+    };
+
+    if from == 0 || to < from {
+      return Err(anyhow!("Invalid lesson {value}"));
+    }
+
+    Ok(match mode {
+      LessonMode::Blocks => Self {
+        from: convert_lesson(from),
+        to: convert_lesson(to),
+        mode,
+      },
+      LessonMode::Periods => Self { from, to, mode },
+    })
+  }
+}
+
+/// Convert raw lesson to block lesson
+fn convert_lesson(lesson: u8) -> u8 {
+  (lesson + lesson % 2) / 2
+}
+
+impl From<u8> for Lessons {
+  /// A single block.
+  fn from(lesson: u8) -> Self {
+    Self {
+      from: lesson,
+      to: lesson,
+      mode: LessonMode::Blocks,
+    }
+  }
+}
+
+impl Display for Lessons {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let unit = match self.mode {
+      LessonMode::Blocks => "Block",
+      LessonMode::Periods => "Stunde",
+    };
+
+    if self.from == self.to {
+      write!(f, "{}. {unit}", self.from)
+    } else {
+      write!(f, "{}.–{}. {unit}", self.from, self.to)
+    }
+  }
+}
+
+impl FromStr for LessonMode {
+  type Err = anyhow::Error;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "blocks" => Ok(Self::Blocks),
+      "periods" => Ok(Self::Periods),
+      _ => Err(anyhow!(
+        "Unknown lesson mode {value}, expected blocks or periods"
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::lessons::{convert_lesson, LessonMode, Lessons};
+
+  #[test]
+  fn test_convert_lesson() {
+    assert_eq!(1, convert_lesson(1));
+    assert_eq!(1, convert_lesson(2));
+    assert_eq!(2, convert_lesson(3));
+    assert_eq!(2, convert_lesson(4));
+    assert_eq!(3, convert_lesson(5));
+    assert_eq!(3, convert_lesson(6));
+    assert_eq!(4, convert_lesson(7));
+    assert_eq!(4, convert_lesson(8));
+    assert_eq!(5, convert_lesson(9));
+    assert_eq!(5, convert_lesson(10));
+  }
+
+  #[test]
+  fn test_parse() -> anyhow::Result<()> {
+    let blocks = |value| Lessons::parse(value, LessonMode::Blocks);
+    let periods = |value| Lessons::parse(value, LessonMode::Periods);
+
+    assert_eq!(Lessons::from(2), blocks("3.")?);
+    assert_eq!(Lessons::from(3), blocks("5")?);
+    assert_eq!((1, 2), blocks("1 - 4").map(|l| (l.from, l.to))?);
+    assert_eq!((2, 2), blocks("3.-4.").map(|l| (l.from, l.to))?);
+    assert_eq!((5, 5), periods("5").map(|l| (l.from, l.to))?);
+    assert_eq!((3, 4), periods("3.-4.").map(|l| (l.from, l.to))?);
+
+    assert!(blocks("").is_err());
+    assert!(blocks("0.").is_err());
+    assert!(blocks("4.-3.").is_err());
+    assert!(blocks("1./2.").is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_display() -> anyhow::Result<()> {
+    assert_eq!("2. Block", Lessons::from(2).to_string());
+    assert_eq!(
+      "1.–2. Block",
+      Lessons::parse("1 - 4", LessonMode::Blocks)?.to_string()
+    );
+    assert_eq!(
+      "5. Stunde",
+      Lessons::parse("5.", LessonMode::Periods)?.to_string()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_deserialize() -> anyhow::Result<()> {
+    assert_eq!(
+      Lessons {
+        from: 3,
+        to: 4,
+        mode: LessonMode::Periods
+      },
+      serde_json::from_str::<Lessons>(r#"{"from": 3, "to": 4, "mode": "periods"}"#)?
+    );
+
+    Ok(())
+  }
+}
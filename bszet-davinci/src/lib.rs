@@ -1,35 +1,59 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 
 use anyhow::anyhow;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use reqwest::header::LAST_MODIFIED;
-use reqwest::{Client, Url};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, Response, StatusCode, Url};
 use sailfish::TemplateOnce;
 use select::document::Document;
 use sentry::protocol::Event;
 use sentry::types::Uuid;
+use serde::{Deserialize, Serialize};
 use time::format_description::well_known::Rfc2822;
 use time::{Date, OffsetDateTime};
-use tokio::sync::{RwLock, RwLockReadGuard};
-use tracing::{error, info};
-
-use change::Change;
-
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
+use tracing::{error, info, warn};
+
+use change::{Change, Outcome};
+
+use crate::class::Class;
+use crate::courses::Courses;
+use crate::crawl::{
+  check_shrinkage, is_transient, CachedPage, CrawlOutcome, CrawlStats, Incomplete, RetryPolicy,
+  Stats,
+};
+use crate::diff::{distinct, same_classes, Diff};
 use crate::extractor::{extract_date, extract_html_table, extract_next_page, parse};
-use crate::html::SubstitutionPlanTemplate;
-use crate::iteration::get_iteration;
-use crate::timetable::igd21::IGD21;
-use crate::timetable::Lesson;
+use crate::format::iso_date;
+use crate::html::{SubstitutionPlanTemplate, WeekTemplate};
+use crate::iteration::IterationCalendar;
+use crate::kinds::ChangeKinds;
+use crate::lessons::LessonMode;
+use crate::storage::FileStorage;
+use crate::subjects::SubjectDictionary;
+use crate::timetable::{Lesson, Timetables};
 
 static REPLACEMENT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\+(.*) \\((.+)\\)").unwrap());
+static GROUP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("_I(\\d+)$").unwrap());
 
 mod change;
+pub mod class;
+pub mod courses;
+pub mod crawl;
+pub mod diff;
 mod extractor;
+pub mod format;
 mod html;
-mod iteration;
+pub mod iteration;
+pub mod kinds;
+pub mod lessons;
+#[cfg(test)]
+mod mock;
+pub mod storage;
+pub mod subjects;
 #[cfg(test)]
 mod test;
 pub mod timetable;
@@ -39,40 +63,140 @@ pub struct Davinci {
   username: String,
   password: String,
   entrypoint: Url,
+  timetables: Timetables,
+  calendar: IterationCalendar,
+  storage: Option<FileStorage>,
   data: RwLock<Option<Data>>,
+  pages: Mutex<HashMap<Url, CachedPage>>,
+  stats: RwLock<Stats>,
+  retry: RetryPolicy,
+  lesson_mode: LessonMode,
+  change_kinds: ChangeKinds,
+  /// Suspicious snapshot and how often it was crawled in a row
+  suspicious: Mutex<Option<(HashSet<Row>, u32)>>,
+}
+
+struct Crawl {
+  rows: Vec<Row>,
+  last_modified: Option<OffsetDateTime>,
+  stats: CrawlStats,
+  /// Page which failed, all previous pages are crawled
+  error: Option<(Url, anyhow::Error)>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Data {
+  #[serde(with = "time::serde::rfc3339")]
   pub last_checked: OffsetDateTime,
+  #[serde(with = "time::serde::rfc3339::option")]
   pub last_modified: Option<OffsetDateTime>,
   pub rows: HashSet<Row>,
+  /// Changes compared to the previous snapshot
+  #[serde(default)]
+  pub diff: Diff,
 }
 
 impl Davinci {
-  pub fn new(entrypoint: Url, username: String, password: String) -> Self {
+  pub fn new(
+    entrypoint: Url,
+    username: String,
+    password: String,
+    timetables: Timetables,
+    calendar: IterationCalendar,
+    storage: Option<FileStorage>,
+  ) -> Self {
     Self {
       client: Client::new(),
       username,
       password,
       entrypoint,
+      timetables,
+      calendar,
+      storage,
       data: RwLock::new(None),
+      pages: Mutex::new(HashMap::new()),
+      stats: RwLock::new(Stats::default()),
+      retry: RetryPolicy::default(),
+      lesson_mode: LessonMode::default(),
+      change_kinds: ChangeKinds::default(),
+      suspicious: Mutex::new(None),
+    }
+  }
+
+  pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+    self.retry = retry;
+    self
+  }
+
+  /// Whether the lessons of the plan are converted to blocks, the default, or kept as periods.
+  pub fn with_lesson_mode(mut self, lesson_mode: LessonMode) -> Self {
+    self.lesson_mode = lesson_mode;
+    self
+  }
+
+  /// Maps the types of change of the plan, see [`ChangeKinds::load`].
+  pub fn with_change_kinds(mut self, change_kinds: ChangeKinds) -> Self {
+    self.change_kinds = change_kinds;
+    self
+  }
+
+  /// Restores the last snapshot from the storage, returns if there was one.
+  pub async fn restore(&self) -> anyhow::Result<bool> {
+    let Some(storage) = &self.storage else {
+      return Ok(false);
+    };
+
+    match storage.load().await? {
+      None => Ok(false),
+      Some(mut restored) => {
+        restored.resolve(self.timetables.subjects());
+        info!(
+          "Restored {} rows, last checked {}",
+          restored.rows.len(),
+          restored.last_checked
+        );
+        *self.data.write().await = Some(restored);
+        Ok(true)
+      }
     }
   }
 
+  pub fn timetables(&self) -> &Timetables {
+    &self.timetables
+  }
+
+  pub fn calendar(&self) -> &IterationCalendar {
+    &self.calendar
+  }
+
   pub async fn data(&self) -> RwLockReadGuard<'_, Option<Data>> {
     self.data.read().await
   }
 
+  pub async fn stats(&self) -> Stats {
+    *self.stats.read().await
+  }
+
   pub async fn get_applied_timetable(
     &self,
+    class: &str,
     date: Date,
+    courses: &Courses,
   ) -> anyhow::Result<(Option<OffsetDateTime>, Vec<Lesson>, Vec<Row>, u8)> {
-    let iteration =
-      get_iteration(date).ok_or_else(|| anyhow!("Unable to find iteration for date {date}"))?;
+    let (class, timetable) = self
+      .timetables
+      .get(class)
+      .ok_or_else(|| anyhow!("Unknown class {class}"))?;
 
-    let mut day = IGD21
+    let iteration = self
+      .calendar
+      .get_iteration(date)
+      .ok_or_else(|| anyhow!("Unable to find iteration for date {date}"))?;
+
+    let mut day = timetable
       .get(&date.weekday())
-      .unwrap()
+      .map(Vec::as_slice)
+      .unwrap_or_default()
       .iter()
       .filter_map(|lesson| {
         if let Some(l_iteration) = lesson.iteration {
@@ -84,6 +208,12 @@ impl Davinci {
       })
       .collect::<Vec<Lesson>>();
 
+    let electives = self.timetables.electives(&class.name);
+    let attended = day
+      .iter()
+      .map(|lesson| courses.keeps(lesson, &electives))
+      .collect::<Vec<bool>>();
+
     let mut relevant_rows = Vec::new();
 
     let mut last_modified = None;
@@ -94,81 +224,179 @@ impl Davinci {
       // sometimes there is a cancel and than a replacement for the canceled lesson
       for row in &data.rows {
         if let Change::Cancel { .. } = row.change {
-          if apply_change(&date, &mut day, &mut relevant_rows, row) {
+          if apply_change(class, &date, &mut day, &mut relevant_rows, row) {
             continue;
           }
         }
       }
 
-      // alter that apply all other changes
+      // alter that apply all other changes, moves are listed at both of their dates
+      // but applied once
+      let mut moves = Vec::<&Row>::new();
       for row in &data.rows {
-        if let Change::Cancel { .. } = row.change {
-          continue;
+        match row.change {
+          Change::Cancel { .. } => continue,
+          Change::Move { .. } => {
+            // the row at `date` knows place and teachers there best
+            let paired = |other: &Row| {
+              same_classes(&other.class, &row.class) && other.change.same_move(&row.change)
+            };
+            if moves.iter().any(|other| paired(other))
+              || (row.date != date
+                && data
+                  .rows
+                  .iter()
+                  .any(|other| other.date == date && paired(other)))
+            {
+              continue;
+            }
+            moves.push(row);
+          }
+          _ => {}
         }
 
-        if apply_change(&date, &mut day, &mut relevant_rows, row) {
+        if apply_change(class, &date, &mut day, &mut relevant_rows, row) {
           continue;
         }
       }
     }
 
+    // filtered after applying, the other courses help to match the changes,
+    // replacements change the subject so attendance is decided on the timetable
+    if !courses.is_empty() {
+      let mut index = 0;
+      day.retain(|lesson| {
+        let keep = attended
+          .get(index)
+          .copied()
+          .unwrap_or_else(|| courses.keeps(lesson, &electives));
+        index += 1;
+        keep
+      });
+      relevant_rows.retain(|row| courses.affects(row, &electives));
+    }
+
     Ok((last_modified, day, relevant_rows, iteration))
   }
 
+  /// Applied timetables from monday to friday of the week starting at `monday` and the
+  /// rows of the week which could not be applied.
+  pub async fn get_applied_week(
+    &self,
+    class: &str,
+    monday: Date,
+    courses: &Courses,
+  ) -> anyhow::Result<(Option<OffsetDateTime>, Vec<AppliedDay>, Vec<Row>)> {
+    if self.timetables.get(class).is_none() {
+      return Err(anyhow!("Unknown class {class}"));
+    }
+
+    let mut last_modified = None;
+    let mut days = Vec::with_capacity(5);
+    let mut unapplied = Vec::new();
+
+    for offset in 0..5 {
+      let date = monday + time::Duration::days(offset);
+
+      // days without iteration like holidays have no lessons, other errors are returned
+      if self.calendar.get_iteration(date).is_none() {
+        days.push(AppliedDay {
+          date,
+          iteration: None,
+          lessons: Vec::new(),
+        });
+        continue;
+      }
+
+      let (modified, lessons, rows, iteration) =
+        self.get_applied_timetable(class, date, courses).await?;
+      last_modified = modified;
+      days.push(AppliedDay {
+        date,
+        iteration: Some(iteration),
+        lessons,
+      });
+      unapplied.extend(rows);
+    }
+
+    // moves are listed at both of their dates, but reported once
+    Ok((last_modified, days, distinct(&unapplied, |row| row)))
+  }
+
+  pub async fn get_week_html(
+    &self,
+    class: &str,
+    monday: Date,
+    courses: &Courses,
+  ) -> anyhow::Result<String> {
+    let (_, days, _) = self.get_applied_week(class, monday, courses).await?;
+
+    Ok(
+      WeekTemplate {
+        class,
+        monday,
+        days: &days,
+      }
+      .render_once()?,
+    )
+  }
+
   pub async fn get_html(&self, date: &Date, classes: &[&str]) -> anyhow::Result<Option<String>> {
     Ok(match self.data.read().await.as_ref() {
       None => None,
-      Some(data) => {
-        let mut table = data
-          .rows
-          .iter()
-          .filter(|row| &row.date == date)
-          .collect::<Vec<&Row>>();
-
-        table.sort_by(|a, b| a.index.cmp(&b.index));
-
-        let table = table
-          .iter()
-          .map(|row| row.raw.as_slice())
-          .collect::<Vec<&[String]>>();
-
-        Some(
-          SubstitutionPlanTemplate {
-            date: *date,
-            table,
-            classes,
-          }
-          .render_once()?,
-        )
-      }
+      Some(data) => Some(
+        SubstitutionPlanTemplate {
+          date: *date,
+          table: data.table(*date),
+          classes,
+        }
+        .render_once()?,
+      ),
     })
   }
 
-  pub async fn update(&self) -> anyhow::Result<bool> {
-    let mut start_url = self.entrypoint.clone();
-    let mut rows = Vec::new();
-    let mut last_modified = None;
-
-    loop {
-      match self.fetch(start_url, &mut rows).await? {
-        None => break,
-        Some((curr_last_modified, next)) => {
-          if let Some(last_last_modified) = last_modified {
-            if last_last_modified < curr_last_modified {
-              last_modified = Some(curr_last_modified);
-            }
-          } else {
-            last_modified = Some(curr_last_modified);
-          }
+  /// Crawls the substitution plan, returns the difference to the previous snapshot.
+  ///
+  /// Pages are requested conditionally, unchanged pages are taken from the cache.
+  /// The previous snapshot is kept if the crawl is incomplete or lost dates or
+  /// rows, until the same snapshot was crawled often enough in a row.
+  pub async fn update(&self) -> CrawlOutcome {
+    let crawl = self.crawl().await;
+    info!(
+      "Crawled {} pages, {} fetched, {} not modified, {} bytes",
+      crawl.stats.pages, crawl.stats.fetched, crawl.stats.not_modified, crawl.stats.bytes
+    );
+
+    let outcome = match crawl.error {
+      Some((_, error)) if crawl.stats.pages == 0 => CrawlOutcome::Failed(error),
+      Some((url, error)) => CrawlOutcome::Partial(Incomplete::PageFailed {
+        url,
+        pages: crawl.stats.pages,
+        error,
+      }),
+      None => self.apply(crawl.rows, crawl.last_modified).await,
+    };
 
-          start_url = next
-        }
-      };
+    let mut stats = self.stats.write().await;
+    stats.crawls += 1;
+    stats.last_crawl = Some(OffsetDateTime::now_utc());
+    stats.last = crawl.stats;
+    stats.total += crawl.stats;
+    match &outcome {
+      CrawlOutcome::Complete(_) => {}
+      CrawlOutcome::Partial(_) => stats.partial += 1,
+      CrawlOutcome::Failed(_) => stats.failed += 1,
     }
 
+    outcome
+  }
+
+  /// Replaces the snapshot with `rows` unless they look like an incomplete crawl.
+  async fn apply(&self, rows: Vec<Row>, last_modified: Option<OffsetDateTime>) -> CrawlOutcome {
     let now = OffsetDateTime::now_utc();
 
     let mut data = self.data.write().await;
+    let mut suspicious = self.suspicious.lock().await;
 
     let mut hash = HashSet::with_capacity(rows.len());
     for row in rows {
@@ -176,70 +404,238 @@ impl Davinci {
     }
 
     // check if there is a difference
-    if let Some(data) = data.as_mut() {
-      // if !hash.iter().zip(&data.rows).any(|(a, b)| a != b) {
-      if hash == data.rows {
+    let diff = match data.as_mut() {
+      Some(data) if hash == data.rows => {
         data.last_checked = now;
-        return Ok(false);
+        *suspicious = None;
+        Diff::default()
+      }
+      previous => {
+        let diff = match previous {
+          Some(previous) => {
+            if let Some(incomplete) = check_shrinkage(&previous.rows, &hash) {
+              let confirmations = match suspicious.as_mut() {
+                Some((rows, confirmations)) if rows == &hash => {
+                  *confirmations += 1;
+                  *confirmations
+                }
+                _ => {
+                  *suspicious = Some((hash.clone(), 1));
+                  1
+                }
+              };
+
+              if confirmations < self.retry.confirmations {
+                return CrawlOutcome::Partial(incomplete);
+              }
+              warn!(
+                "Accepting snapshot after {} crawls: {}",
+                confirmations, incomplete
+              );
+            }
+            Diff::between(&previous.rows, &hash)
+          }
+          None => Diff::between(&HashSet::new(), &hash),
+        };
+        *suspicious = None;
+        *data = Some(Data {
+          last_checked: now,
+          last_modified,
+          rows: hash,
+          diff: diff.clone(),
+        });
+        diff
+      }
+    };
+
+    if let (Some(storage), Some(data)) = (&self.storage, data.as_ref()) {
+      if let Err(err) = storage.save(data).await {
+        error!("Unable to persist substitution plan: {}", err);
+      }
+    }
+
+    CrawlOutcome::Complete(diff)
+  }
+
+  async fn crawl(&self) -> Crawl {
+    let mut pages = self.pages.lock().await;
+    let mut visited = HashSet::new();
+    let mut url = self.entrypoint.clone();
+    let mut crawl = Crawl {
+      rows: Vec::new(),
+      last_modified: None,
+      stats: CrawlStats::default(),
+      error: None,
+    };
+    // rows are numbered per page, a day may continue on the next page
+    let mut indices = HashMap::<Date, u8>::new();
+    let mut previous = None::<Row>;
+
+    loop {
+      if !visited.insert(url.clone()) {
+        crawl.error = Some((url.clone(), anyhow!("page {url} was already crawled")));
+        return crawl;
+      }
+
+      let page = match self
+        .fetch(&url, previous.as_ref(), &mut pages, &mut crawl.stats)
+        .await
+      {
+        Ok(page) => page,
+        Err(err) => {
+          crawl.error = Some((url, err));
+          return crawl;
+        }
+      };
+      crawl.stats.pages += 1;
+      for row in &page.rows {
+        let index = indices.entry(row.date).or_default();
+        crawl.rows.push(Row {
+          index: *index,
+          ..row.clone()
+        });
+        *index += 1;
+      }
+      crawl.last_modified = crawl.last_modified.max(Some(page.last_modified));
+      if let Some(last) = page.rows.last() {
+        previous = Some(last.clone());
+      }
+
+      match &page.next {
+        None => break,
+        Some(next) => url = next.clone(),
       }
     }
 
-    *data = Some(Data {
-      last_checked: now,
-      last_modified,
-      rows: hash,
-    });
+    // forget pages which are no longer part of the substitution plan
+    pages.retain(|url, _| visited.contains(url));
 
-    Ok(true)
+    crawl
   }
 
-  async fn fetch(
+  /// Fetches the page at `url`, or takes it from the cache if it was not modified. `previous` is
+  /// the last row of the page before, which the first rows may continue.
+  async fn fetch<'a>(
     &self,
-    url: Url,
-    rows: &mut Vec<Row>,
-  ) -> anyhow::Result<Option<(OffsetDateTime, Url)>> {
-    let response = self
-      .client
-      .get(url.clone())
-      .basic_auth(&self.username, Some(&self.password))
-      .send()
-      .await?
-      .error_for_status()?;
+    url: &Url,
+    previous: Option<&Row>,
+    pages: &'a mut HashMap<Url, CachedPage>,
+    stats: &mut CrawlStats,
+  ) -> anyhow::Result<&'a CachedPage> {
+    // a page continuing a changed row is outdated, even if the page itself was not modified
+    let cached = pages
+      .get(url)
+      .filter(|page| page.continued.is_none() || page.continued.as_ref() == previous);
+
+    let mut attempt = 0;
+    let response = loop {
+      match self.request(url, cached).await {
+        Ok(response) => break response,
+        Err(err) => {
+          attempt += 1;
+          if attempt >= self.retry.attempts || !is_transient(&err) {
+            return Err(err.into());
+          }
+
+          let delay = self.retry.delay(attempt);
+          warn!("Unable to crawl {}, retrying in {:?}: {}", url, delay, err);
+          tokio::time::sleep(delay).await;
+        }
+      }
+    };
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+      stats.not_modified += 1;
+      info!("Not modified {}", url);
+      return pages
+        .get(url)
+        .ok_or_else(|| anyhow!("{url} is not modified, but not cached"));
+    }
 
-    let last_modified = match response.headers().get(LAST_MODIFIED) {
+    let last_modified_header = match response.headers().get(LAST_MODIFIED) {
       None => return Err(anyhow!("last-modified http header is required")),
-      Some(value) => OffsetDateTime::parse(value.to_str()?, &Rfc2822)?,
+      Some(value) => value.clone(),
     };
+    let last_modified = OffsetDateTime::parse(last_modified_header.to_str()?, &Rfc2822)?;
+    let etag = response.headers().get(ETAG).cloned();
 
     info!("Crawled {}, last modified {}", url, last_modified);
 
     let text = response.text().await?;
+    stats.fetched += 1;
+    stats.bytes += text.len() as u64;
+
     let doc = Document::from(text.as_str());
 
     let date = extract_date(&doc)?;
 
+    let mut rows = Vec::new();
     let table = extract_html_table(&doc);
-    parse(table, &date, rows)?;
-
-    Ok(match extract_next_page(&doc) {
+    let continued = parse(
+      table,
+      &date,
+      self.lesson_mode,
+      &self.change_kinds,
+      self.timetables.subjects(),
+      previous,
+      &mut rows,
+    )?;
+
+    let next = match extract_next_page(&doc) {
       None => None,
-      Some(next) => {
-        let next = url.join(next)?;
-        if next == url {
-          None
-        } else {
-          Some((last_modified, next))
-        }
+      Some(next) => Some(url.join(next)?).filter(|next| next != url),
+    };
+
+    pages.insert(
+      url.clone(),
+      CachedPage {
+        etag,
+        last_modified_header,
+        last_modified,
+        rows,
+        continued: previous.filter(|_| continued).cloned(),
+        next,
+      },
+    );
+
+    Ok(&pages[url])
+  }
+
+  async fn request(
+    &self,
+    url: &Url,
+    cached: Option<&CachedPage>,
+  ) -> Result<Response, reqwest::Error> {
+    let mut request = self
+      .client
+      .get(url.clone())
+      .basic_auth(&self.username, Some(&self.password));
+
+    if let Some(cached) = cached {
+      request = request.header(IF_MODIFIED_SINCE, cached.last_modified_header.clone());
+      if let Some(etag) = &cached.etag {
+        request = request.header(IF_NONE_MATCH, etag.clone());
       }
-    })
+    }
+
+    request.send().await?.error_for_status()
   }
 }
 
+/// Applied timetable of a day, days without iteration have no lessons.
 #[derive(Clone, Debug)]
+pub struct AppliedDay {
+  pub date: Date,
+  pub iteration: Option<u8>,
+  pub lessons: Vec<Lesson>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Row {
   /// IF YOU ADD PROPERTIES, UPDATE IMPLEMENTATIONS BELOW
   // ignored for Eq, PartialEq and Hash
   pub index: u8,
+  #[serde(with = "iso_date")]
   pub date: Date,
   pub class: Vec<String>,
   pub change: Change,
@@ -247,6 +643,70 @@ pub struct Row {
   pub raw: Vec<String>,
 }
 
+impl Row {
+  /// Course of the affected lesson, taken from subject codes like `LF13D_I1`.
+  pub fn group(&self) -> Option<&str> {
+    // subject column of the raw layout
+    let subject = self.raw.get(2)?;
+    let subject = match REPLACEMENT_REGEX.captures(subject) {
+      Some(captures) => captures.get(2)?.as_str(),
+      None => subject.as_str(),
+    };
+
+    GROUP_REGEX
+      .captures(subject.trim())
+      .map(|captures| captures.get(1).unwrap().as_str())
+  }
+}
+
+impl Data {
+  /// Restored subjects only know their id, takes their labels from `subjects`.
+  fn resolve(&mut self, subjects: &SubjectDictionary) {
+    self.rows = std::mem::take(&mut self.rows)
+      .into_iter()
+      .map(|mut row| {
+        row.change.resolve(subjects);
+        row
+      })
+      .collect();
+
+    let diff = &mut self.diff;
+    for row in diff.added.iter_mut().chain(diff.removed.iter_mut()).chain(
+      diff
+        .modified
+        .iter_mut()
+        .flat_map(|modification| [&mut modification.before, &mut modification.after]),
+    ) {
+      row.change.resolve(subjects);
+    }
+  }
+
+  /// Dates the substitution plan has rows for, in ascending order.
+  pub fn dates(&self) -> Vec<Date> {
+    let dates = self
+      .rows
+      .iter()
+      .map(|row| row.date)
+      .collect::<HashSet<Date>>();
+    let mut dates = dates.into_iter().collect::<Vec<Date>>();
+    dates.sort();
+    dates
+  }
+
+  /// Raw columns of the rows of `date` in the order of the substitution plan.
+  pub fn table(&self, date: Date) -> Vec<&[String]> {
+    let mut rows = self
+      .rows
+      .iter()
+      .filter(|row| row.date == date)
+      .collect::<Vec<&Row>>();
+
+    rows.sort_by_key(|row| row.index);
+
+    rows.iter().map(|row| row.raw.as_slice()).collect()
+  }
+}
+
 impl Hash for Row {
   fn hash<H: Hasher>(&self, state: &mut H) {
     self.date.hash(state);
@@ -264,31 +724,34 @@ impl PartialEq<Self> for Row {
 impl Eq for Row {}
 
 fn apply_change(
+  class: &Class,
   date: &Date,
   day: &mut Vec<Lesson>,
   relevant_rows: &mut Vec<Row>,
   row: &Row,
 ) -> bool {
-  if &row.date != date
-    || !(row.class.contains(&"IGD21".to_string()) || row.class.contains(&"IGD 21".to_string()))
-  {
+  let on_date = match &row.change {
+    Change::Move { from, to, .. } => &from.date == date || &to.date == date,
+    _ => &row.date == date,
+  };
+  if !on_date || !class.affected_by(&row.class) {
     return true;
   }
 
-  match row.change.apply(day) {
-    Ok(applied) => {
-      if applied {
-        return true;
-      }
+  let message = match row.change.apply_on(date, day, row.group()) {
+    Outcome::Applied => return true,
+    Outcome::NotFound => format!("Unable to apply change: {row:?}"),
+    Outcome::Ambiguous(candidates) => {
+      warn!("Change matches {} lessons: {}", candidates, row.change);
+      format!("Ambiguous change, {candidates} lessons match: {row:?}")
     }
-    Err(err) => error!("Could not apply row: {}", err),
-  }
+  };
 
   {
     let uuid = Uuid::new_v4();
     let event = Event {
       event_id: uuid,
-      message: Some(format!("Unable to apply change: {row:?}")),
+      message: Some(message),
       level: sentry::protocol::Level::Warning,
       ..Default::default()
     };
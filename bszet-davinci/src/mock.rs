@@ -0,0 +1,205 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use axum::extract::{Path as UrlPath, State};
+use axum::headers::authorization::Basic;
+use axum::headers::{Authorization, ETag, IfModifiedSince, IfNoneMatch, LastModified};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Router, Server, TypedHeader};
+use reqwest::Url;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Stand-in for the DaVinci export at geschuetzt.bszet.de, serving the
+/// pages of a fixture directory with basic auth, `Last-Modified` and `ETag`
+/// headers and conditional requests.
+pub(crate) struct MockDavinci {
+  addr: SocketAddr,
+  state: Arc<MockState>,
+  server: JoinHandle<()>,
+}
+
+struct MockState {
+  username: String,
+  password: String,
+  pages: RwLock<HashMap<String, Page>>,
+  requests: AtomicUsize,
+  not_modified: AtomicUsize,
+  etags: AtomicBool,
+  /// Status and number of the next requests of a page to fail
+  failures: Mutex<HashMap<String, (StatusCode, usize)>>,
+}
+
+#[derive(Clone)]
+struct Page {
+  body: String,
+  last_modified: OffsetDateTime,
+  etag: ETag,
+}
+
+impl MockDavinci {
+  pub(crate) async fn start(username: &str, password: &str) -> anyhow::Result<Self> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let state = Arc::new(MockState {
+      username: username.to_string(),
+      password: password.to_string(),
+      pages: RwLock::new(HashMap::new()),
+      requests: AtomicUsize::new(0),
+      not_modified: AtomicUsize::new(0),
+      etags: AtomicBool::new(true),
+      failures: Mutex::new(HashMap::new()),
+    });
+
+    let router = Router::new()
+      .route("/:page", get(page))
+      .with_state(state.clone());
+    let server = Server::from_tcp(listener)?.serve(router.into_make_service());
+
+    let server = tokio::spawn(async move {
+      server.await.unwrap();
+    });
+
+    Ok(Self {
+      addr,
+      state,
+      server,
+    })
+  }
+
+  /// Url of the first page.
+  pub(crate) fn entrypoint(&self) -> Url {
+    format!("http://{}/V_DC_001.html", self.addr)
+      .parse()
+      .unwrap()
+  }
+
+  /// Replaces all pages with the html files of the fixture directory `name`.
+  pub(crate) async fn load(&self, name: &str, last_modified: OffsetDateTime) -> anyhow::Result<()> {
+    let mut pages = HashMap::new();
+
+    for entry in std::fs::read_dir(fixture(name))? {
+      let path = entry?.path();
+      let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+      let body = std::fs::read_to_string(&path)?;
+
+      let mut hasher = DefaultHasher::new();
+      body.hash(&mut hasher);
+      let etag = format!("\"{:x}\"", hasher.finish()).parse()?;
+
+      pages.insert(
+        file_name,
+        Page {
+          body,
+          last_modified,
+          etag,
+        },
+      );
+    }
+
+    *self.state.pages.write().await = pages;
+
+    Ok(())
+  }
+
+  /// Number of requests served, including rejected ones.
+  pub(crate) fn requests(&self) -> usize {
+    self.state.requests.load(Ordering::SeqCst)
+  }
+
+  /// Number of requests answered with `304 Not Modified`.
+  pub(crate) fn not_modified(&self) -> usize {
+    self.state.not_modified.load(Ordering::SeqCst)
+  }
+
+  /// Answers the next `times` requests of page `name` with `status`.
+  pub(crate) fn fail(&self, name: &str, status: StatusCode, times: usize) {
+    self
+      .state
+      .failures
+      .lock()
+      .unwrap()
+      .insert(name.to_string(), (status, times));
+  }
+
+  /// Whether to send `ETag` headers, without them only `If-Modified-Since` is evaluated.
+  pub(crate) fn set_etags(&self, etags: bool) {
+    self.state.etags.store(etags, Ordering::SeqCst);
+  }
+}
+
+impl Drop for MockDavinci {
+  fn drop(&mut self) {
+    self.server.abort();
+  }
+}
+
+pub(crate) fn fixture(name: &str) -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("fixtures")
+    .join(name)
+}
+
+async fn page(
+  State(state): State<Arc<MockState>>,
+  UrlPath(name): UrlPath<String>,
+  authorization: Option<TypedHeader<Authorization<Basic>>>,
+  if_none_match: Option<TypedHeader<IfNoneMatch>>,
+  if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+) -> Response {
+  state.requests.fetch_add(1, Ordering::SeqCst);
+
+  match authorization {
+    Some(TypedHeader(Authorization(basic)))
+      if basic.username() == state.username && basic.password() == state.password => {}
+    _ => return StatusCode::UNAUTHORIZED.into_response(),
+  }
+
+  if let Some((status, times)) = state.failures.lock().unwrap().get_mut(&name) {
+    if *times > 0 {
+      *times -= 1;
+      return status.into_response();
+    }
+  }
+
+  let pages = state.pages.read().await;
+  let Some(page) = pages.get(&name) else {
+    return StatusCode::NOT_FOUND.into_response();
+  };
+
+  let etags = state.etags.load(Ordering::SeqCst);
+  let last_modified = SystemTime::from(page.last_modified);
+
+  // If-None-Match takes precedence over If-Modified-Since, see RFC 9110 13.2.2
+  let not_modified = match (if_none_match, if_modified_since) {
+    (Some(TypedHeader(if_none_match)), _) if etags => {
+      !if_none_match.precondition_passes(&page.etag)
+    }
+    (_, Some(TypedHeader(if_modified_since))) => !if_modified_since.is_modified(last_modified),
+    _ => false,
+  };
+
+  if not_modified {
+    state.not_modified.fetch_add(1, Ordering::SeqCst);
+    return StatusCode::NOT_MODIFIED.into_response();
+  }
+
+  (
+    [(CONTENT_TYPE, "text/html; charset=utf-8")],
+    TypedHeader(LastModified::from(last_modified)),
+    etags.then(|| TypedHeader(page.etag.clone())),
+    page.body.clone(),
+  )
+    .into_response()
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use crate::Data;
+
+/// Stores the last accepted snapshot of the substitution plan as json file,
+/// so it survives restarts.
+pub struct FileStorage {
+  path: PathBuf,
+}
+
+impl FileStorage {
+  pub fn new(path: PathBuf) -> Self {
+    Self { path }
+  }
+
+  pub async fn load(&self) -> anyhow::Result<Option<Data>> {
+    if !fs::try_exists(&self.path).await? {
+      return Ok(None);
+    }
+
+    let content = fs::read(&self.path).await?;
+    Ok(Some(serde_json::from_slice(&content)?))
+  }
+
+  /// Writes to a temporary file first and moves it in place afterwards,
+  /// to not corrupt the previous snapshot if writing fails.
+  pub async fn save(&self, data: &Data) -> anyhow::Result<()> {
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent).await?;
+    }
+
+    let tmp = self.path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_vec(data)?).await?;
+    fs::rename(&tmp, &self.path).await?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashSet;
+
+  use time::{Date, Month, OffsetDateTime};
+
+  use crate::change::Change;
+  use crate::diff::Diff;
+  use crate::storage::FileStorage;
+  use crate::subjects::SubjectDictionary;
+  use crate::timetable::Subject;
+  use crate::{Data, Row};
+
+  #[tokio::test]
+  async fn test_save_and_load() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("bszet-davinci-{}.json", std::process::id()));
+    let storage = FileStorage::new(path.clone());
+
+    assert!(storage.load().await?.is_none());
+
+    let data = Data {
+      last_checked: OffsetDateTime::now_utc(),
+      last_modified: None,
+      rows: HashSet::from([Row {
+        index: 0,
+        date: Date::from_calendar_date(2023, Month::September, 25)?,
+        class: vec!["IGD21".to_string()],
+        change: Change::new(2, "Fällt aus", "DEU", "B6".to_string(), "Mül", None)?,
+        raw: vec!["IGD21".to_string(), "3.".to_string()],
+      }]),
+      diff: Diff::default(),
+    };
+    storage.save(&data).await?;
+
+    let mut loaded = storage.load().await?.unwrap();
+    std::fs::remove_file(path)?;
+
+    assert_eq!(data.last_checked, loaded.last_checked);
+    assert_eq!(data.rows, loaded.rows);
+    assert!(matches!(
+      &loaded.rows.iter().next().unwrap().change,
+      Change::Cancel { subject, .. } if *subject == Subject::id("GermanBasic")
+    ));
+
+    // subjects are stored by id, their labels are taken from the dictionary
+    loaded.resolve(&SubjectDictionary::default());
+    assert_eq!(
+      "2. Block D fällt aus",
+      loaded.rows.iter().next().unwrap().change.to_string()
+    );
+
+    assert_eq!("null", serde_json::to_string(&Subject::None)?);
+    assert_eq!(Subject::None, serde_json::from_str("null")?);
+
+    Ok(())
+  }
+}
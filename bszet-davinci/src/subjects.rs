@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use sentry::protocol::Event;
+use sentry::types::Uuid;
+use serde::Deserialize;
+
+use crate::format::{self, Format};
+use crate::timetable::Subject;
+
+/// Subject of the dictionary, a timetable or plan refers to it by one of its `codes`.
+///
+/// Subjects are identified by their `id`, labels may differ between dictionaries.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SubjectInfo {
+  /// Stored in snapshots, must not change
+  pub id: String,
+  pub codes: Vec<String>,
+  /// Abbreviation shown in tables and messages
+  pub short: String,
+  pub name: String,
+  pub color: Option<String>,
+  /// Whether students can choose it as course
+  #[serde(default = "selectable")]
+  pub selectable: bool,
+}
+
+fn selectable() -> bool {
+  true
+}
+
+impl SubjectInfo {
+  /// Subject only known by its id, its labels are the id until it is
+  /// [resolved](SubjectDictionary::resolve).
+  pub fn unresolved(id: &str) -> Self {
+    Self {
+      id: id.to_string(),
+      codes: Vec::new(),
+      short: id.to_string(),
+      name: id.to_string(),
+      color: None,
+      selectable: false,
+    }
+  }
+}
+
+impl PartialEq for SubjectInfo {
+  fn eq(&self, other: &Self) -> bool {
+    self.id == other.id
+  }
+}
+
+impl Eq for SubjectInfo {}
+
+impl Hash for SubjectInfo {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.id.hash(state);
+  }
+}
+
+#[derive(Deserialize)]
+struct SubjectsFile {
+  subjects: Vec<SubjectInfo>,
+}
+
+/// Subjects of a school, the default is the shipped `subjects.toml`.
+#[derive(Debug)]
+pub struct SubjectDictionary {
+  subjects: Vec<Arc<SubjectInfo>>,
+  ids: HashMap<String, usize>,
+  codes: HashMap<String, usize>,
+}
+
+impl SubjectDictionary {
+  pub fn new(subjects: Vec<SubjectInfo>) -> anyhow::Result<Self> {
+    let mut ids = HashMap::new();
+    let mut codes = HashMap::new();
+
+    for (index, subject) in subjects.iter().enumerate() {
+      if subject.id.is_empty() {
+        return Err(anyhow!("Empty subject id"));
+      }
+      if ids.insert(subject.id.clone(), index).is_some() {
+        return Err(anyhow!("Duplicate subject id {}", subject.id));
+      }
+
+      for code in &subject.codes {
+        if code.is_empty() {
+          return Err(anyhow!("Empty code of subject {}", subject.id));
+        }
+        if codes.insert(code.clone(), index).is_some() {
+          return Err(anyhow!("Duplicate subject code {}", code));
+        }
+      }
+    }
+
+    Ok(Self {
+      subjects: subjects.into_iter().map(Arc::new).collect(),
+      ids,
+      codes,
+    })
+  }
+
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    Self::new(format::load::<SubjectsFile>(path)?.subjects)
+      .map_err(|err| anyhow!("{}: {}", path.display(), err))
+  }
+
+  pub fn get(&self, id: &str) -> Option<&Arc<SubjectInfo>> {
+    self.ids.get(id).map(|index| &self.subjects[*index])
+  }
+
+  pub fn by_code(&self, code: &str) -> Option<&Arc<SubjectInfo>> {
+    self.codes.get(code).map(|index| &self.subjects[*index])
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Arc<SubjectInfo>> {
+    self.subjects.iter()
+  }
+
+  /// Known subject by id.
+  pub fn subject(&self, id: &str) -> Option<Subject> {
+    self.get(id).cloned().map(Subject::Known)
+  }
+
+  /// Subject of the code, an empty code is no subject.
+  pub fn from_code(&self, code: &str) -> Option<Subject> {
+    if code.is_empty() {
+      return Some(Subject::None);
+    }
+
+    self.by_code(code).cloned().map(Subject::Known)
+  }
+
+  /// Subject of a code of the plan, codes missing in the dictionary are kept as they are.
+  pub fn parse(&self, code: &str) -> Subject {
+    match self.from_code(code) {
+      Some(subject) => subject,
+      None => {
+        {
+          let uuid = Uuid::new_v4();
+          let event = Event {
+            event_id: uuid,
+            message: Some(format!("Unknown subject: {code:?}")),
+            level: sentry::protocol::Level::Info,
+            ..Default::default()
+          };
+
+          sentry::capture_event(event);
+        }
+
+        Subject::Other(code.to_string())
+      }
+    }
+  }
+
+  /// Takes the labels of the dictionary for a subject restored by id.
+  pub fn resolve(&self, subject: &mut Subject) {
+    match subject {
+      Subject::Known(info) => {
+        if let Some(known) = self.get(&info.id) {
+          *info = known.clone();
+        }
+      }
+      Subject::Cancel(inner) => self.resolve(inner),
+      Subject::None | Subject::Other(_) => {}
+    }
+  }
+}
+
+impl Default for SubjectDictionary {
+  /// The shipped `subjects.toml`.
+  fn default() -> Self {
+    let file = Format::Toml
+      .parse::<SubjectsFile>(include_str!("../../subjects.toml"))
+      .expect("Invalid built-in subjects.toml");
+    Self::new(file.subjects).expect("Invalid built-in subjects.toml")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::subjects::{SubjectDictionary, SubjectInfo};
+
+  fn info(id: &str, codes: &[&str]) -> SubjectInfo {
+    SubjectInfo {
+      id: id.to_string(),
+      codes: codes.iter().map(|code| code.to_string()).collect(),
+      short: id.to_string(),
+      name: id.to_string(),
+      color: None,
+      selectable: true,
+    }
+  }
+
+  #[test]
+  fn test_dictionary() -> anyhow::Result<()> {
+    let subjects = SubjectDictionary::default();
+    assert_eq!("Ma", subjects.by_code("MA").unwrap().short);
+    assert_eq!("Lf11", subjects.by_code("LF11D_I2").unwrap().id);
+    assert_eq!("Lernfeld 11", subjects.get("Lf11").unwrap().name);
+    assert!(!subjects.get("FaeVerb").unwrap().selectable);
+    assert!(subjects.by_code("LF99").is_none());
+
+    let subjects = SubjectDictionary::new(vec![info("Lf14", &["LF14D_I1", "LF14D_I2"])])?;
+    assert_eq!("Lf14", subjects.by_code("LF14D_I2").unwrap().id);
+
+    assert!(SubjectDictionary::new(vec![info("Ma", &["MA"]), info("Ma", &["M"])]).is_err());
+    assert!(SubjectDictionary::new(vec![info("Ma", &["MA"]), info("Mb", &["MA"])]).is_err());
+    assert!(SubjectDictionary::new(vec![info("", &["X"])]).is_err());
+
+    Ok(())
+  }
+}
@@ -1,6 +1,66 @@
-use crate::Davinci;
+use std::path::Path;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+
+use time::macros::datetime;
+use time::{Date, Month, OffsetDateTime};
+
+use crate::change::Change;
+use crate::courses::Courses;
+use crate::crawl::{CrawlOutcome, CrawlStats, Incomplete, RetryPolicy};
+use crate::iteration::{Holiday, IterationCalendar};
+use crate::lessons::{LessonMode, Lessons};
+use crate::mock::MockDavinci;
+use crate::subjects::SubjectDictionary;
+use crate::timetable::{Lesson, Subject, Timetables};
+use crate::{Data, Davinci, Row};
+
+fn davinci(mock: &MockDavinci, password: &str) -> anyhow::Result<Davinci> {
+  let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+
+  Ok(
+    Davinci::new(
+      mock.entrypoint(),
+      "bszet".to_string(),
+      password.to_string(),
+      Timetables::load(&root.join("timetables"), SubjectDictionary::default())?,
+      school_year(),
+      None,
+    )
+    .with_retry_policy(RetryPolicy {
+      attempts: 3,
+      backoff: Duration::from_millis(10),
+      confirmations: 2,
+    }),
+  )
+}
+
+/// The school year 2023/24 the pages of the mock are from.
+fn school_year() -> IterationCalendar {
+  let date = |year, month, day| Date::from_calendar_date(year, month, day).unwrap();
+
+  IterationCalendar::new(
+    date(2023, Month::August, 21),
+    Some(date(2024, Month::June, 19)),
+    1,
+    vec![Holiday {
+      name: Some("Herbstferien".to_string()),
+      from: date(2023, Month::October, 2),
+      to: date(2023, Month::October, 14),
+    }],
+  )
+}
+
+fn date(day: u8) -> Date {
+  Date::from_calendar_date(2023, Month::September, day).unwrap()
+}
+
+const PUBLISHED: OffsetDateTime = datetime!(2023-09-01 14:32 UTC);
+const UPDATED: OffsetDateTime = datetime!(2023-09-03 18:05 UTC);
 
 #[tokio::test]
+#[ignore = "requires credentials for geschuetzt.bszet.de"]
 async fn test_load() -> anyhow::Result<()> {
   let davinci = Davinci::new(
     "https://geschuetzt.bszet.de/s-lk-vw/Vertretungsplaene/V_PlanBGy/V_DC_001.html"
@@ -8,12 +68,20 @@ async fn test_load() -> anyhow::Result<()> {
       .unwrap(),
     "".to_string(),
     "".to_string(),
+    Timetables::default(),
+    IterationCalendar::new(
+      Date::from_calendar_date(2023, Month::August, 21)?,
+      None,
+      1,
+      vec![],
+    ),
+    None,
   );
 
-  println!("{:?}", davinci.update().await?);
+  println!("{:?}", davinci.update().await.into_result()?);
 
-  // assert_eq!(true, davinci.update().await?);
-  // assert_eq!(false, davinci.update().await?);
+  // assert_eq!(true, davinci.update().await.into_result()?);
+  // assert_eq!(false, davinci.update().await.into_result()?);
 
   for row in &davinci.data().await.as_ref().unwrap().rows {
     println!("- {:?}", row);
@@ -21,3 +89,584 @@ async fn test_load() -> anyhow::Result<()> {
 
   Ok(())
 }
+
+#[tokio::test]
+async fn test_update() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+
+  let diff = davinci.update().await.into_result()?;
+  assert_eq!(5, diff.added.len());
+  assert!(diff.removed.is_empty());
+  assert!(diff.modified.is_empty());
+  // both pages, the second one links to itself
+  assert_eq!(2, mock.requests());
+
+  {
+    let data = davinci.data().await;
+    let data = data.as_ref().unwrap();
+    assert_eq!(Some(PUBLISHED), data.last_modified);
+    assert_eq!(vec![date(4), date(5)], data.dates());
+  }
+
+  assert!(davinci.update().await.into_result()?.is_empty());
+
+  mock.load("2023-09-05", UPDATED).await?;
+  let diff = davinci.update().await.into_result()?;
+
+  assert_eq!(1, diff.added.len());
+  assert_eq!(date(5), diff.added[0].date);
+  assert_eq!(1, diff.removed.len());
+  assert_eq!(date(4), diff.removed[0].date);
+  assert_eq!(1, diff.modified.len());
+  assert_eq!(
+    "2. Block Ch Raumänderung B9 → B12",
+    diff.modified[0].after.change.to_string()
+  );
+  assert_eq!(
+    Some(UPDATED),
+    davinci.data().await.as_ref().unwrap().last_modified
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_applied_timetable() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  davinci.update().await.into_result()?;
+
+  let (last_modified, monday, unknown, iteration) = davinci
+    .get_applied_timetable("IGD 21", date(4), &Courses::default())
+    .await?;
+  assert_eq!(Some(PUBLISHED), last_modified);
+  assert_eq!(1, iteration);
+  assert!(unknown.is_empty());
+
+  assert_eq!(
+    Subject::Cancel(Box::new(Subject::id("GermanBasic"))),
+    monday[0].subject
+  );
+  assert_eq!(Some("Aufgaben im Lernraum"), monday[0].notice.as_deref());
+  assert_eq!(Subject::id("Chemistry"), monday[1].subject);
+  assert_eq!(Some("B11"), monday[1].place.as_deref());
+  // teachers are taken from the plan, the shipped timetables have none
+  assert_eq!(vec!["Mül".to_string()], monday[0].teachers);
+  assert_eq!(vec!["Sch".to_string()], monday[1].teachers);
+
+  let (_, tuesday, unknown, _) = davinci
+    .get_applied_timetable("IGD21", date(5), &Courses::default())
+    .await?;
+  assert!(unknown.is_empty());
+  assert!(tuesday
+    .iter()
+    .any(|lesson| lesson.lesson == 1 && lesson.subject == Subject::id("Physics")));
+  assert!(tuesday
+    .iter()
+    .any(|lesson| lesson.lesson == 4 && lesson.subject == Subject::id("Literature")));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_courses() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  davinci.update().await.into_result()?;
+
+  // physics replaces basic math, which is not attended with english
+  let dictionary = davinci.timetables().subjects();
+  let courses = Courses::parse("LK-Ma, En, Ru, Gruppe 2", dictionary)?;
+  let (_, tuesday, _, _) = davinci
+    .get_applied_timetable("IGD21", date(5), &courses)
+    .await?;
+  let subjects = tuesday
+    .iter()
+    .map(|lesson| (lesson.lesson, lesson.subject.clone()))
+    .collect::<Vec<(u8, Subject)>>();
+  assert!(subjects.contains(&(1, Subject::id("EnglishBasic"))));
+  assert!(!subjects.contains(&(1, Subject::id("Physics"))));
+  assert!(subjects.contains(&(3, Subject::id("Lf11"))));
+  assert!(!subjects.contains(&(3, Subject::id("Lf13"))));
+  assert!(subjects.contains(&(4, Subject::id("MathAdvanced"))));
+  assert!(!subjects.contains(&(4, Subject::id("EnglishAdvanced"))));
+  // literature is an elective as well, so the addition is not attended
+  assert!(!subjects.contains(&(4, Subject::id("Literature"))));
+
+  let (_, tuesday, _, _) = davinci
+    .get_applied_timetable("IGD21", date(5), &Courses::parse("Ma", dictionary)?)
+    .await?;
+  assert!(tuesday
+    .iter()
+    .any(|lesson| lesson.lesson == 1 && lesson.subject == Subject::id("Physics")));
+  assert!(!tuesday
+    .iter()
+    .any(|lesson| lesson.subject == Subject::id("EnglishBasic")));
+
+  let electives = davinci.timetables().electives("IGD 21");
+  assert!(electives.contains(&Subject::id("Russian")));
+  assert!(!electives.contains(&Subject::id("GermanBasic")));
+  assert!(!electives.contains(&Subject::id("Lf11")));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_html() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+
+  assert!(davinci.get_html(&date(4), &["IGD21"]).await?.is_none());
+  davinci.update().await.into_result()?;
+
+  let html = davinci
+    .get_html(&date(4), &["IGD 21", "IGD21"])
+    .await?
+    .unwrap();
+  assert!(html.contains("Monday der 04. September 2023"));
+  // both rows of IGD 21 are highlighted, IGD22 is not
+  assert_eq!(2, html.matches("class=\"selected\"").count());
+  assert!(html.contains("Aufgaben im Lernraum"));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+
+  assert!(davinci(&mock, "wrong")?
+    .update()
+    .await
+    .into_result()
+    .is_err());
+
+  mock.load("invalid", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  assert!(davinci.update().await.into_result().is_err());
+  assert!(davinci.data().await.is_none());
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_conditional_requests() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+
+  davinci.update().await.into_result()?;
+  let stats = davinci.stats().await;
+  assert_eq!(1, stats.crawls);
+  assert_eq!(2, stats.last.pages);
+  assert_eq!(2, stats.last.fetched);
+  assert_eq!(0, stats.last.not_modified);
+  assert!(stats.last.bytes > 0);
+
+  // unchanged pages are taken from the cache
+  assert!(davinci.update().await.into_result()?.is_empty());
+  let stats = davinci.stats().await;
+  assert_eq!(
+    CrawlStats {
+      pages: 2,
+      fetched: 0,
+      not_modified: 2,
+      bytes: 0,
+    },
+    stats.last
+  );
+  assert_eq!(4, stats.total.pages);
+  assert_eq!(2, mock.not_modified());
+  assert_eq!(5, davinci.data().await.as_ref().unwrap().rows.len());
+
+  // the etag changes with the content, even if last-modified does not
+  mock.load("2023-09-05", PUBLISHED).await?;
+  assert!(!davinci.update().await.into_result()?.is_empty());
+  assert_eq!(2, davinci.stats().await.last.fetched);
+
+  // without etags the server falls back to the modification date
+  mock.set_etags(false);
+  mock.load("2023-09-04", UPDATED).await?;
+  assert!(!davinci.update().await.into_result()?.is_empty());
+  assert_eq!(2, davinci.stats().await.last.fetched);
+  assert!(davinci.update().await.into_result()?.is_empty());
+  assert_eq!(2, davinci.stats().await.last.not_modified);
+  assert_eq!(4, mock.not_modified());
+
+  mock.load("invalid", UPDATED + time::Duration::HOUR).await?;
+  assert!(davinci.update().await.into_result().is_err());
+  let stats = davinci.stats().await;
+  assert_eq!(6, stats.crawls);
+  assert_eq!(1, stats.failed);
+  assert_eq!(10, stats.total.pages);
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_retry() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+
+  mock.fail("V_DC_002.html", StatusCode::NOT_FOUND, 2);
+  assert_eq!(5, davinci.update().await.into_result()?.added.len());
+  assert_eq!(4, mock.requests());
+
+  // the previous snapshot is kept if a page fails after all retries
+  mock.load("2023-09-05", UPDATED).await?;
+  mock.fail("V_DC_002.html", StatusCode::SERVICE_UNAVAILABLE, 3);
+  assert!(matches!(
+    davinci.update().await,
+    CrawlOutcome::Partial(Incomplete::PageFailed { pages: 1, .. })
+  ));
+  assert_eq!(
+    Some(PUBLISHED),
+    davinci.data().await.as_ref().unwrap().last_modified
+  );
+
+  mock.fail("V_DC_001.html", StatusCode::BAD_GATEWAY, 3);
+  assert!(matches!(davinci.update().await, CrawlOutcome::Failed(_)));
+
+  let stats = davinci.stats().await;
+  assert_eq!(3, stats.crawls);
+  assert_eq!(1, stats.partial);
+  assert_eq!(1, stats.failed);
+
+  // recovered
+  let diff = davinci.update().await.into_result()?;
+  assert_eq!(1, diff.added.len());
+  assert_eq!(1, diff.modified.len());
+
+  // authentication errors are not retried
+  let requests = mock.requests();
+  let unauthorized = self::davinci(&mock, "wrong")?;
+  assert!(matches!(
+    unauthorized.update().await,
+    CrawlOutcome::Failed(_)
+  ));
+  assert_eq!(requests + 1, mock.requests());
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_shrinkage() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("2023-09-04", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  davinci.update().await.into_result()?;
+
+  // tuesday vanished, but monday is still there
+  mock.load("shrunk", UPDATED).await?;
+  assert!(matches!(
+    davinci.update().await,
+    CrawlOutcome::Partial(Incomplete::MissingDates(dates)) if dates == vec![date(5)]
+  ));
+  assert_eq!(
+    vec![date(4), date(5)],
+    davinci.data().await.as_ref().unwrap().dates()
+  );
+
+  // accepted once it was crawled twice in a row
+  let diff = davinci.update().await.into_result()?;
+  assert_eq!(2, diff.removed.len());
+  assert_eq!(
+    vec![date(4)],
+    davinci.data().await.as_ref().unwrap().dates()
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_column_layout() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("reordered", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  davinci.update().await.into_result()?;
+
+  let data = davinci.data().await;
+  let data = data.as_ref().unwrap();
+  assert_eq!(2, data.rows.len());
+
+  let mut rows = data.rows.iter().collect::<Vec<&Row>>();
+  rows.sort_by_key(|row| row.index);
+
+  assert_eq!("1. Block D fällt aus", rows[0].change.to_string());
+  assert_eq!(vec!["IGD 21".to_string()], rows[1].class);
+  match &rows[1].change {
+    Change::Replacement { teachers, .. } => {
+      assert_eq!(Some(vec!["Kol".to_string()]), teachers.from);
+      assert_eq!(vec!["Sch".to_string()], teachers.to);
+    }
+    change => panic!("expected replacement, got {change:?}"),
+  }
+
+  // raw rows keep the layout of the template
+  assert_eq!(
+    vec!["IGD 21", "1.", "DEU", "B6", "Mül", "Fällt aus", ""],
+    data.table(date(4))[0]
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_moves() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("moved", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  let diff = davinci.update().await.into_result()?;
+
+  // both rows are the same move
+  assert_eq!(2, diff.added.len());
+  assert!(diff.added[0].change.same_move(&diff.added[1].change));
+  assert_eq!(1, diff.merge_moves().added.len());
+  assert_eq!(
+    "Ch verschoben vom 04.09. 2. Block auf den 06.09. 4. Block",
+    diff.added[0].change.to_string()
+  );
+
+  let (_, monday, unknown, _) = davinci
+    .get_applied_timetable("IGD21", date(4), &Courses::default())
+    .await?;
+  assert!(unknown.is_empty());
+  let chemistry = monday.iter().find(|lesson| lesson.lesson == 2).unwrap();
+  assert_eq!(
+    Subject::Cancel(Box::new(Subject::id("Chemistry"))),
+    chemistry.subject
+  );
+  assert_eq!(
+    Some("Auf 06.09. 4. Block verschoben"),
+    chemistry.notice.as_deref()
+  );
+
+  let (_, wednesday, unknown, _) = davinci
+    .get_applied_timetable("IGD21", date(6), &Courses::default())
+    .await?;
+  assert!(unknown.is_empty());
+  let moved = wednesday
+    .iter()
+    .filter(|lesson| lesson.subject == Subject::id("Chemistry"))
+    .collect::<Vec<&Lesson>>();
+  assert_eq!(1, moved.len());
+  assert_eq!(4, moved[0].lesson);
+  assert_eq!(Some("B12"), moved[0].place.as_deref());
+
+  Ok(())
+}
+
+/// Class and lessons of the first row of the second page are left out, it continues the day.
+#[tokio::test]
+async fn test_continued_page() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("continued", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  assert_eq!(4, davinci.update().await.into_result()?.added.len());
+
+  let fourth = Lessons::parse("4.", LessonMode::Blocks)?;
+  let continued = |data: &Data| {
+    let row = data.rows.iter().find(|row| row.index == 2).unwrap().clone();
+    (row.class, row.change.lessons())
+  };
+  assert_eq!(
+    (vec!["IGD21".to_string()], fourth),
+    continued(davinci.data().await.as_ref().unwrap())
+  );
+
+  // the second page is parsed again once the row it continues changes
+  mock.load("recontinued", PUBLISHED).await?;
+  davinci.update().await.into_result()?;
+  assert_eq!(2, davinci.stats().await.last.fetched);
+  assert_eq!(
+    (vec!["IGD22".to_string()], fourth),
+    continued(davinci.data().await.as_ref().unwrap())
+  );
+
+  assert!(davinci.update().await.into_result()?.is_empty());
+  assert_eq!(2, davinci.stats().await.last.not_modified);
+
+  Ok(())
+}
+
+/// Synthetic pages after the layout of the export, which splits long days across pages,
+/// pads empty cells and lists moves and rows of several classes.
+#[tokio::test]
+async fn test_export() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("export", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  let diff = davinci.update().await.into_result()?;
+  assert_eq!(4, mock.requests());
+  assert_eq!(10, diff.added.len());
+  // the move is listed at both of its dates
+  assert_eq!(9, diff.merge_moves().added.len());
+
+  {
+    let data = davinci.data().await;
+    let data = data.as_ref().unwrap();
+    assert_eq!(vec![date(11), date(12), date(13)], data.dates());
+
+    // tuesday continues on the third page
+    let tuesday = data.table(date(12));
+    assert_eq!(5, tuesday.len());
+    assert_eq!(
+      vec!["IGD21", "1.-2.", "+PHY (MA)", "B05", "Wei", "Vertreten", ""],
+      tuesday[0]
+    );
+    assert_eq!(
+      vec!["IGD21", "7.-8.", "LIT", "B4", "Ric", "Zusatzunterricht", ""],
+      tuesday[3]
+    );
+    assert_eq!(
+      vec!["IVS22", "", "ENG", "A102", "Ric", "Fällt aus", ""],
+      tuesday[4]
+    );
+
+    let mut shared = data
+      .rows
+      .iter()
+      .filter(|row| row.class.len() > 1)
+      .collect::<Vec<&Row>>();
+    shared.sort_by_key(|row| row.date);
+    let shared = shared
+      .iter()
+      .map(|row| row.class.clone())
+      .collect::<Vec<Vec<String>>>();
+    assert_eq!(
+      vec![
+        vec!["IGD21".to_string(), "IGD 22".to_string()],
+        vec!["IGD21".to_string(), "IGD22".to_string()],
+      ],
+      shared
+    );
+  }
+
+  let (_, monday, unknown, iteration) = davinci
+    .get_applied_timetable("IGD21", date(11), &Courses::default())
+    .await?;
+  assert!(unknown.is_empty());
+  assert_eq!(2, iteration);
+  assert_eq!(
+    Subject::Cancel(Box::new(Subject::id("GermanBasic"))),
+    monday[0].subject
+  );
+  assert_eq!(Some("Aufgaben im Lernraum"), monday[0].notice.as_deref());
+  assert_eq!(
+    Subject::Cancel(Box::new(Subject::id("Chemistry"))),
+    monday[1].subject
+  );
+  assert_eq!(
+    Some("Auf 13.09. 5. Block verschoben"),
+    monday[1].notice.as_deref()
+  );
+
+  let (_, tuesday, unknown, _) = davinci
+    .get_applied_timetable("IGD 21", date(12), &Courses::default())
+    .await?;
+  assert!(unknown.is_empty());
+  let physics = tuesday
+    .iter()
+    .find(|lesson| lesson.lesson == 1 && lesson.subject == Subject::id("Physics"))
+    .unwrap();
+  // the absent teacher has a column of its own
+  assert_eq!(vec!["Wei".to_string()], physics.teachers);
+  let german = tuesday.iter().find(|lesson| lesson.lesson == 2).unwrap();
+  assert_eq!(Some("A102"), german.place.as_deref());
+  assert!(tuesday
+    .iter()
+    .any(|lesson| lesson.lesson == 4 && lesson.subject == Subject::id("Literature")));
+
+  // the exam is not a known type of change
+  let (_, wednesday, unknown, _) = davinci
+    .get_applied_timetable("IGD21", date(13), &Courses::default())
+    .await?;
+  assert_eq!(1, unknown.len());
+  assert_eq!(vec!["IGD21", "IGD22"], unknown[0].class);
+  let chemistry = wednesday
+    .iter()
+    .find(|lesson| lesson.subject == Subject::id("Chemistry"))
+    .unwrap();
+  assert_eq!(5, chemistry.lesson);
+  assert_eq!(Some("B12"), chemistry.place.as_deref());
+
+  let html = davinci.get_html(&date(12), &["IGD21"]).await?.unwrap();
+  assert_eq!(3, html.matches("class=\"selected\"").count());
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn test_week() -> anyhow::Result<()> {
+  let mock = MockDavinci::start("bszet", "secret").await?;
+  mock.load("moved", PUBLISHED).await?;
+  let davinci = davinci(&mock, "secret")?;
+  davinci.update().await.into_result()?;
+
+  let (last_modified, days, unapplied) = davinci
+    .get_applied_week("IGD 21", date(4), &Courses::default())
+    .await?;
+  assert_eq!(Some(PUBLISHED), last_modified);
+  assert!(unapplied.is_empty());
+  assert_eq!(
+    (4..=8).map(date).collect::<Vec<Date>>(),
+    days.iter().map(|day| day.date).collect::<Vec<Date>>()
+  );
+  assert!(days.iter().all(|day| day.iteration == Some(1)));
+  assert!(days[2]
+    .lessons
+    .iter()
+    .any(|lesson| lesson.lesson == 4 && lesson.subject == Subject::id("Chemistry")));
+
+  let html = davinci
+    .get_week_html("IGD21", date(4), &Courses::default())
+    .await?;
+  assert!(html.contains("Woche von IGD21 ab 04.09.2023"));
+  assert!(html.contains("Monday 04.09."));
+  assert!(html.contains("Auf 06.09. 4. Block verschoben"));
+  // the move is highlighted at both of its dates
+  assert_eq!(2, html.matches("class=\"changed\"").count());
+
+  // autumn holidays
+  let (_, days, _) = davinci
+    .get_applied_week(
+      "IGD21",
+      Date::from_calendar_date(2023, Month::October, 2)?,
+      &Courses::default(),
+    )
+    .await?;
+  assert!(days
+    .iter()
+    .all(|day| day.iteration.is_none() && day.lessons.is_empty()));
+  assert!(davinci
+    .get_applied_week("IGD99", date(4), &Courses::default())
+    .await
+    .is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_row_group() {
+  let row = |subject: &str| Row {
+    index: 0,
+    date: date(5),
+    class: vec!["IGD21".to_string()],
+    change: Change::new(2, "Fällt aus", subject, "B5".to_string(), "Mül", None).unwrap(),
+    raw: ["IGD21", "3.", subject, "B5", "Mül", "Fällt aus", ""]
+      .map(str::to_string)
+      .to_vec(),
+  };
+
+  assert_eq!(Some("1"), row("LF13D_I1").group());
+  assert_eq!(Some("2"), row("+CH (LF11D_I2)").group());
+  assert_eq!(None, row("LF11D").group());
+  assert_eq!(None, row("+LF11D_I1 (MA)").group());
+}
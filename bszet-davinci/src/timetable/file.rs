@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use time::Weekday;
+
+use crate::class::Class;
+use crate::format::load;
+use crate::subjects::SubjectDictionary;
+use crate::timetable::{Lesson, Timetable};
+
+/// On-disk representation of a class timetable.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TimetableFile {
+  class: String,
+  #[serde(default)]
+  aliases: Vec<String>,
+  #[serde(default)]
+  monday: Vec<LessonFile>,
+  #[serde(default)]
+  tuesday: Vec<LessonFile>,
+  #[serde(default)]
+  wednesday: Vec<LessonFile>,
+  #[serde(default)]
+  thursday: Vec<LessonFile>,
+  #[serde(default)]
+  friday: Vec<LessonFile>,
+  #[serde(default)]
+  saturday: Vec<LessonFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LessonFile {
+  lesson: u8,
+  iteration: Option<u8>,
+  subject: String,
+  place: Option<String>,
+  #[serde(default)]
+  teachers: Vec<String>,
+  group: Option<String>,
+}
+
+impl TimetableFile {
+  /// Reads, parses and validates the timetable at `path`.
+  pub(crate) fn load(
+    path: &Path,
+    subjects: &SubjectDictionary,
+  ) -> anyhow::Result<(Class, Timetable)> {
+    load::<Self>(path)?
+      .into_timetable(subjects)
+      .map_err(|err| anyhow!("Invalid timetable {}: {}", path.display(), err))
+  }
+
+  pub(crate) fn into_timetable(
+    self,
+    subjects: &SubjectDictionary,
+  ) -> anyhow::Result<(Class, Timetable)> {
+    if self.class.trim().is_empty() || self.aliases.iter().any(|alias| alias.trim().is_empty()) {
+      return Err(anyhow!("class names must not be empty"));
+    }
+
+    let mut timetable = HashMap::new();
+
+    for (weekday, lessons) in [
+      (Weekday::Monday, self.monday),
+      (Weekday::Tuesday, self.tuesday),
+      (Weekday::Wednesday, self.wednesday),
+      (Weekday::Thursday, self.thursday),
+      (Weekday::Friday, self.friday),
+      (Weekday::Saturday, self.saturday),
+    ] {
+      let day = lessons
+        .into_iter()
+        .map(|lesson| {
+          lesson
+            .into_lesson(subjects)
+            .map_err(|err| anyhow!("{weekday}: {err}"))
+        })
+        .collect::<anyhow::Result<Vec<Lesson>>>()?;
+
+      if !day.is_empty() {
+        timetable.insert(weekday, day);
+      }
+    }
+
+    Ok((Class::new(self.class, self.aliases), timetable))
+  }
+}
+
+impl LessonFile {
+  fn into_lesson(self, subjects: &SubjectDictionary) -> anyhow::Result<Lesson> {
+    if self.lesson == 0 {
+      return Err(anyhow!("lesson numbers start at 1"));
+    }
+
+    if let Some(iteration) = self.iteration {
+      if iteration != 1 && iteration != 2 {
+        return Err(anyhow!(
+          "lesson {} has iteration {}, expected 1 or 2",
+          self.lesson,
+          iteration
+        ));
+      }
+    }
+
+    let subject = subjects.from_code(&self.subject).ok_or_else(|| {
+      anyhow!(
+        "lesson {} has unknown subject code {:?}",
+        self.lesson,
+        self.subject
+      )
+    })?;
+
+    Ok(Lesson {
+      lesson: self.lesson,
+      planned: Some(subject.clone()),
+      subject,
+      iteration: self.iteration,
+      place: self.place,
+      teachers: self.teachers,
+      group: self.group,
+      notice: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::Path;
+
+  use time::Weekday;
+
+  use crate::format::Format;
+  use crate::subjects::{SubjectDictionary, SubjectInfo};
+  use crate::timetable::file::TimetableFile;
+  use crate::timetable::{Subject, Timetables};
+
+  #[test]
+  fn test_load_shipped_timetables() -> anyhow::Result<()> {
+    let timetables = Timetables::load(
+      &Path::new(env!("CARGO_MANIFEST_DIR")).join("../timetables"),
+      SubjectDictionary::default(),
+    )?;
+    let (class, igd21) = timetables.get("IGD 21").unwrap();
+
+    assert_eq!("IGD21", class.name);
+    assert_eq!(5, igd21.len());
+    assert_eq!(8, igd21[&Weekday::Monday].len());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_toml() -> anyhow::Result<()> {
+    let (class, timetable) = Format::Toml
+      .parse::<TimetableFile>(
+        r#"
+        class = "IGD21"
+        aliases = ["IGD 21"]
+
+        [[monday]]
+        lesson = 1
+        subject = "DEU"
+        place = "B6"
+
+        [[monday]]
+        lesson = 3
+        iteration = 2
+        subject = "LF11D"
+        place = "B5"
+        teachers = ["Mül"]
+        group = "1"
+      "#,
+      )?
+      .into_timetable(&SubjectDictionary::default())?;
+
+    assert_eq!("IGD21", class.name);
+    assert_eq!(vec!["IGD 21".to_string()], class.aliases);
+    assert_eq!(1, timetable.len());
+
+    let monday = &timetable[&Weekday::Monday];
+    assert_eq!(2, monday.len());
+    assert_eq!(Subject::id("GermanBasic"), monday[0].subject);
+    assert_eq!(Some(2), monday[1].iteration);
+    assert_eq!(vec!["Mül".to_string()], monday[1].teachers);
+    assert_eq!(Some("1".to_string()), monday[1].group);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_json_and_yaml() -> anyhow::Result<()> {
+    let (_, json) = Format::Json
+      .parse::<TimetableFile>(
+        r#"{"class": "IGD21", "friday": [{"lesson": 1, "subject": "PHY", "place": "B112"}]}"#,
+      )?
+      .into_timetable(&SubjectDictionary::default())?;
+    let (_, yaml) = Format::Yaml
+      .parse::<TimetableFile>(
+        "class: IGD21\nfriday:\n  - lesson: 1\n    subject: PHY\n    place: B112\n",
+      )?
+      .into_timetable(&SubjectDictionary::default())?;
+
+    assert_eq!(Subject::id("Physics"), json[&Weekday::Friday][0].subject);
+    assert_eq!(Subject::id("Physics"), yaml[&Weekday::Friday][0].subject);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_own_dictionary() -> anyhow::Result<()> {
+    let subjects = SubjectDictionary::new(vec![SubjectInfo {
+      id: "German".to_string(),
+      codes: vec!["DEU".to_string()],
+      short: "Deu".to_string(),
+      name: "Deutsch".to_string(),
+      color: None,
+      selectable: true,
+    }])?;
+    let file = "class = \"IGD21\"\n[[monday]]\nlesson = 1\nsubject = \"DEU\"";
+
+    let (_, timetable) = Format::Toml
+      .parse::<TimetableFile>(file)?
+      .into_timetable(&subjects)?;
+    let subject = &timetable[&Weekday::Monday][0].subject;
+    assert_eq!(&Subject::id("German"), subject);
+    assert_eq!("Deu", subject.to_string());
+
+    let (_, timetable) = Format::Toml
+      .parse::<TimetableFile>(file)?
+      .into_timetable(&SubjectDictionary::default())?;
+    assert_eq!(
+      Subject::id("GermanBasic"),
+      timetable[&Weekday::Monday][0].subject
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_validation() {
+    let invalid = [
+      "class = \"\"",
+      "class = \"IGD21\"\naliases = [\"\"]",
+      "class = \"IGD21\"\n[[monday]]\nlesson = 0\nsubject = \"DEU\"",
+      "class = \"IGD21\"\n[[monday]]\nlesson = 1\niteration = 3\nsubject = \"DEU\"",
+      "class = \"IGD21\"\n[[monday]]\nlesson = 1\nsubject = \"UNKNOWN\"",
+      "class = \"IGD21\"\n[[sunday]]\nlesson = 1\nsubject = \"DEU\"",
+    ];
+
+    let subjects = SubjectDictionary::default();
+    for content in invalid {
+      assert!(
+        Format::Toml
+          .parse::<TimetableFile>(content)
+          .and_then(|file| file.into_timetable(&subjects))
+          .is_err(),
+        "{content}"
+      );
+    }
+  }
+}
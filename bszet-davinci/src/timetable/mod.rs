@@ -1,110 +1,172 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter, Write};
+use std::fs::read_dir;
+use std::path::Path;
+use std::sync::Arc;
 
+use anyhow::anyhow;
 use sentry::protocol::Event;
 use sentry::types::Uuid;
+use serde::{Deserialize, Serialize};
 use time::Weekday;
-use tracing::warn;
+use tracing::{info, warn};
 
-pub mod igd21;
+use crate::class::{Class, ClassRegistry};
+use crate::courses::electives;
+use crate::format::Format;
+use crate::subjects::{SubjectDictionary, SubjectInfo};
+use crate::timetable::file::TimetableFile;
+
+mod file;
 
 #[derive(Clone, Debug)]
 pub struct Lesson {
   pub lesson: u8,
   pub subject: Subject,
+  /// Subject of the timetable, unlike `subject` not replaced by changes, `None` for added lessons
+  pub planned: Option<Subject>,
   pub iteration: Option<u8>,
   pub place: Option<String>,
+  pub teachers: Vec<String>,
+  pub group: Option<String>,
   pub notice: Option<String>,
 }
 
-type Day = Vec<Lesson>;
+pub type Day = Vec<Lesson>;
+
+pub type Timetable = HashMap<Weekday, Day>;
+
+/// Base timetables of all configured classes, indexed by canonical class name.
+#[derive(Debug, Default)]
+pub struct Timetables {
+  subjects: SubjectDictionary,
+  classes: ClassRegistry,
+  timetables: HashMap<String, Timetable>,
+}
+
+impl Timetables {
+  /// No timetables yet, subject codes are looked up in `subjects`.
+  pub fn new(subjects: SubjectDictionary) -> Self {
+    Self {
+      subjects,
+      ..Self::default()
+    }
+  }
+
+  /// Loads every `.toml`, `.json`, `.yaml` and `.yml` file inside of `dir`.
+  pub fn load(dir: &Path, subjects: SubjectDictionary) -> anyhow::Result<Self> {
+    let mut timetables = Self::new(subjects);
+
+    for entry in read_dir(dir)? {
+      let path = entry?.path();
+      if !path.is_file() || Format::from_path(&path).is_none() {
+        continue;
+      }
 
-type Timetable = HashMap<Weekday, Day>;
+      let (class, timetable) = TimetableFile::load(&path, &timetables.subjects)?;
+      info!(
+        "Loaded timetable of class {} from {}",
+        class,
+        path.display()
+      );
+      timetables
+        .insert(class, timetable)
+        .map_err(|err| anyhow!("{}: {}", path.display(), err))?;
+    }
+
+    Ok(timetables)
+  }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+  pub fn insert(&mut self, class: Class, timetable: Timetable) -> anyhow::Result<()> {
+    let name = class.name.clone();
+    self.classes.insert(class)?;
+    self.timetables.insert(name, timetable);
+    Ok(())
+  }
+
+  /// Looks up the timetable by canonical class name or alias.
+  pub fn get(&self, class: &str) -> Option<(&Class, &Timetable)> {
+    let class = self.classes.resolve(class)?;
+    Some((class, self.timetables.get(&class.name)?))
+  }
+
+  /// Subjects of `class` only some students attend, see [`Courses`](crate::courses::Courses).
+  pub fn electives(&self, class: &str) -> HashSet<Subject> {
+    self
+      .get(class)
+      .map(|(_, timetable)| electives(timetable))
+      .unwrap_or_default()
+  }
+
+  pub fn classes(&self) -> &ClassRegistry {
+    &self.classes
+  }
+
+  /// Dictionary of the subject codes of timetables and plan.
+  pub fn subjects(&self) -> &SubjectDictionary {
+    &self.subjects
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(from = "StoredSubject", into = "StoredSubject")]
 pub enum Subject {
-  GermanBasic,
-  GermanAdvanced,
-  MathBasic,
-  MathAdvanced,
-  EnglishBasic,
-  EnglishAdvanced,
-  Art,
-  History,
-  French,
-  Ethics,
-  Russian,
-  Chemistry,
-  Physics,
-  /// PE
-  PhysicalEducation,
-  Literature,
-
-  Lf6_7_9,
-  Lf9_12,
-  Lf8,
-  Lf10,
-  Lf11,
-  Lf13,
-
-  FaeVerb,
+  /// Subject of the [dictionary](SubjectDictionary), compared by id
+  Known(Arc<SubjectInfo>),
   None,
 
   Cancel(Box<Subject>),
   Other(String),
 }
 
-impl From<&str> for Subject {
-  fn from(value: &str) -> Self {
-    match value {
-      "DEU" => Self::GermanBasic,
-      "LK-DEU" => Self::GermanAdvanced,
-      "MA" => Self::MathBasic,
-      "LK-MA" => Self::MathAdvanced,
-      "ENG" => Self::EnglishBasic,
-      "LK-ENG" => Self::EnglishAdvanced,
-      "BK" => Self::Art,
-      "BK1" => Self::Art,
-      "BK2" => Self::Art,
-      "GGK" => Self::History,
-      "F-B" => Self::French,
-      "ETH" => Self::Ethics,
-      "R-B" => Self::Russian,
-      "CH" => Self::Chemistry,
-      "PHY" => Self::Physics,
-      "SP" => Self::PhysicalEducation,
-      "LIT" => Self::Literature,
-
-      "LF 6+7+9" => Self::Lf6_7_9,
-      "LF 9+12" => Self::Lf9_12,
-      "IS-GP" => Self::Lf9_12,
-      "LF8D_I1" => Self::Lf8,
-      "LF8D_I2" => Self::Lf8,
-      "LF10D_I1" => Self::Lf10,
-      "LF10D_I2" => Self::Lf10,
-      "LF11D" => Self::Lf11,
-      "LF11D_I1" => Self::Lf11,
-      "LF11D_I2" => Self::Lf11,
-      "LF13D_I1" => Self::Lf13,
-      "LF13D_I2" => Self::Lf13,
-
-      "_fä.verb." => Self::FaeVerb,
-      "" => Self::None,
-      other => {
-        {
-          let uuid = Uuid::new_v4();
-          let event = Event {
-            event_id: uuid,
-            message: Some(format!("Unknown subject: {other:?}")),
-            level: sentry::protocol::Level::Info,
-            ..Default::default()
-          };
+/// Known subjects are stored by their id, like the variants they replace, no subject as `null`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredSubject {
+  Empty,
+  Id(String),
+  Tagged(TaggedSubject),
+}
 
-          sentry::capture_event(event);
-        }
+#[derive(Serialize, Deserialize)]
+enum TaggedSubject {
+  Cancel(Box<Subject>),
+  Other(String),
+}
 
-        Self::Other(other.to_string())
-      }
+impl From<StoredSubject> for Subject {
+  fn from(stored: StoredSubject) -> Self {
+    match stored {
+      StoredSubject::Empty => Self::None,
+      StoredSubject::Id(id) => Self::id(&id),
+      StoredSubject::Tagged(TaggedSubject::Cancel(subject)) => Self::Cancel(subject),
+      StoredSubject::Tagged(TaggedSubject::Other(other)) => Self::Other(other),
+    }
+  }
+}
+
+impl From<Subject> for StoredSubject {
+  fn from(subject: Subject) -> Self {
+    match subject {
+      Subject::Known(info) => Self::Id(info.id.clone()),
+      Subject::None => Self::Empty,
+      Subject::Cancel(subject) => Self::Tagged(TaggedSubject::Cancel(subject)),
+      Subject::Other(other) => Self::Tagged(TaggedSubject::Other(other)),
+    }
+  }
+}
+
+impl Subject {
+  /// Known subject by id only, see [`SubjectInfo::unresolved`].
+  pub fn id(id: &str) -> Self {
+    Self::Known(Arc::new(SubjectInfo::unresolved(id)))
+  }
+
+  /// Labels and color of known subjects.
+  pub fn info(&self) -> Option<&SubjectInfo> {
+    match self {
+      Self::Known(info) => Some(info),
+      _ => None,
     }
   }
 }
@@ -112,28 +174,7 @@ impl From<&str> for Subject {
 impl Display for Subject {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
-      Self::GermanBasic => f.write_str("D"),
-      Self::GermanAdvanced => f.write_str("LK-D"),
-      Self::MathBasic => f.write_str("Ma"),
-      Self::MathAdvanced => f.write_str("LK-Ma"),
-      Self::EnglishBasic => f.write_str("En"),
-      Self::EnglishAdvanced => f.write_str("LK-En"),
-      Self::Art => f.write_str("BK"),
-      Self::History => f.write_str("Ge"),
-      Self::French => f.write_str("Frz"),
-      Self::Ethics => f.write_str("Eth"),
-      Self::Russian => f.write_str("Ru"),
-      Self::Chemistry => f.write_str("Ch"),
-      Self::Physics => f.write_str("Ph"),
-      Self::PhysicalEducation => f.write_str("Sp"),
-      Self::Literature => f.write_str("Lit"),
-      Self::Lf6_7_9 => f.write_str("LF 6+7+9"),
-      Self::Lf9_12 => f.write_str("LF 9+12"),
-      Self::Lf8 => f.write_str("LF 8"),
-      Self::Lf10 => f.write_str("LF 10"),
-      Self::Lf11 => f.write_str("LF 11"),
-      Self::Lf13 => f.write_str("LF 13"),
-      Self::FaeVerb => f.write_str("Fä-Verb"),
+      Self::Known(info) => f.write_str(&info.short),
       Self::None => f.write_str("None"),
       Self::Cancel(inner) => {
         f.write_char('(')?;
@@ -160,15 +201,3 @@ impl Display for Subject {
     }
   }
 }
-
-impl Lesson {
-  pub fn new(lesson: u8, iteration: Option<u8>, subject: Subject, place: &str) -> Self {
-    Self {
-      lesson,
-      iteration,
-      subject,
-      place: Some(place.to_string()),
-      notice: None,
-    }
-  }
-}
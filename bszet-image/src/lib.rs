@@ -1,10 +1,62 @@
+use async_trait::async_trait;
 use fantoccini::{Client, ClientBuilder, Locator};
 use hyper::client::HttpConnector;
+use time::Date;
+use url::Url;
+
+pub use native::NativeRenderer;
+
+mod native;
+
+/// Substitution plan of a single day, rows of `classes` are highlighted.
+pub struct Plan {
+  pub date: Date,
+  /// Raw columns of the substitution plan rows
+  pub rows: Vec<Vec<String>>,
+  pub classes: Vec<String>,
+}
+
+/// Applied timetables of a week as grid, one column per day and one row per lesson.
+pub struct WeekPlan {
+  pub class: String,
+  pub monday: Date,
+  /// Selected courses, empty for all lessons
+  pub courses: String,
+  pub days: Vec<WeekDay>,
+}
+
+pub struct WeekDay {
+  /// Heading like `Monday 04.09.`
+  pub title: String,
+  /// Cells of the lessons, the first one is lesson 1
+  pub cells: Vec<WeekCell>,
+}
+
+#[derive(Default)]
+pub struct WeekCell {
+  pub lines: Vec<String>,
+  /// Highlights cancelled or changed lessons
+  pub changed: bool,
+}
+
+/// Renders substitution plans to PNG images.
+#[async_trait]
+pub trait ImageRenderer: Send + Sync {
+  async fn render(&self, plan: &Plan) -> anyhow::Result<Vec<u8>>;
+
+  async fn render_week(&self, week: &WeekPlan) -> anyhow::Result<Vec<u8>>;
+}
 
 pub struct WebToImageConverter {
   client: Client,
 }
 
+/// Screenshots the html plan served at `base_url` using a WebDriver.
+pub struct WebRenderer {
+  gecko_driver_url: Url,
+  base_url: Url,
+}
+
 impl WebToImageConverter {
   pub async fn new(gecko_driver_url: &str) -> anyhow::Result<Self> {
     let client = ClientBuilder::new(HttpConnector::new())
@@ -34,6 +86,53 @@ impl WebToImageConverter {
   }
 }
 
+impl WebRenderer {
+  pub fn new(gecko_driver_url: Url, base_url: Url) -> Self {
+    Self {
+      gecko_driver_url,
+      base_url,
+    }
+  }
+
+  async fn screenshot(&self, url: &Url) -> anyhow::Result<Vec<u8>> {
+    let converter = WebToImageConverter::new(self.gecko_driver_url.as_str()).await?;
+    let image = converter.create_image(url.as_str()).await;
+    converter.close().await?;
+
+    image
+  }
+}
+
+#[async_trait]
+impl ImageRenderer for WebRenderer {
+  async fn render(&self, plan: &Plan) -> anyhow::Result<Vec<u8>> {
+    let mut url = self.base_url.join(&format!(
+      "davinci/{}-{:0>2}-{:0>2}",
+      plan.date.year(),
+      plan.date.month() as u8,
+      plan.date.day(),
+    ))?;
+    url
+      .query_pairs_mut()
+      .append_pair("class", &plan.classes.join(","));
+
+    self.screenshot(&url).await
+  }
+
+  async fn render_week(&self, week: &WeekPlan) -> anyhow::Result<Vec<u8>> {
+    let (year, number, _) = week.monday.to_iso_week_date();
+    let mut url = self
+      .base_url
+      .join(&format!("davinci/week/{year}-W{number:0>2}/"))?
+      .join(&week.class)?;
+    if !week.courses.is_empty() {
+      url.query_pairs_mut().append_pair("courses", &week.courses);
+    }
+
+    self.screenshot(&url).await
+  }
+}
+
 #[cfg(test)]
 mod test {
   use std::fs::File;
@@ -41,13 +140,14 @@ mod test {
 
   use crate::WebToImageConverter;
 
-  fn write_to_file(file_name: &str, data: &Vec<u8>) -> std::io::Result<()> {
+  pub(crate) fn write_to_file(file_name: &str, data: &[u8]) -> std::io::Result<()> {
     let mut file = File::create(file_name)?;
     file.write_all(data)?;
     Ok(())
   }
 
   #[tokio::test]
+  #[ignore = "requires a running geckodriver"]
   async fn open_selenium() -> anyhow::Result<()> {
     let web_to_image_convert = WebToImageConverter::new("http://127.0.0.1:4444").await?;
 
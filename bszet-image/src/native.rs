@@ -0,0 +1,640 @@
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use async_trait::async_trait;
+use tiny_skia::{Color, Paint, Pixmap, PremultipliedColorU8, Rect, Transform};
+
+use crate::{ImageRenderer, Plan, WeekPlan};
+
+// mirrors the styles of the html plan at a window width of 1500px
+const WIDTH: f32 = 1500.0;
+const PADDING: f32 = 16.0;
+const FONT_SIZE: f32 = 38.0;
+const HEADING_SIZE: f32 = 76.0;
+const HEADING_MARGIN: f32 = 8.0;
+const CELL_PADDING_X: f32 = 4.8;
+const CELL_PADDING_Y: f32 = 1.6;
+
+const HEADER: [&str; 7] = [
+  "Klasse",
+  "Std.",
+  "Fach",
+  "Raum",
+  "Lehrkraft",
+  "Art",
+  "Mitteilung",
+];
+
+const BACKGROUND: [u8; 3] = [0xff, 0xff, 0xff];
+const TEXT: [u8; 3] = [0x00, 0x00, 0x00];
+const HEADER_BACKGROUND: [u8; 3] = [0xee, 0x67, 0x23];
+const HEADER_TEXT: [u8; 3] = [0xff, 0xff, 0xff];
+const EVEN: [u8; 3] = [0xf6, 0xf6, 0xf6];
+const ODD: [u8; 3] = [0xe5, 0xe5, 0xe5];
+const SELECTED_EVEN: [u8; 3] = [0xff, 0xa9, 0x92];
+const SELECTED_ODD: [u8; 3] = [0xff, 0x81, 0x63];
+
+/// Draws the substitution plan in process, without the need of a WebDriver.
+pub struct NativeRenderer {
+  regular: FontVec,
+  bold: FontVec,
+}
+
+struct Line<'a> {
+  text: String,
+  font: &'a FontVec,
+}
+
+struct Cell<'a> {
+  lines: Vec<Line<'a>>,
+}
+
+impl NativeRenderer {
+  /// Accepts TrueType, OpenType or WOFF2 fonts.
+  pub fn new(regular: &[u8], bold: &[u8]) -> anyhow::Result<Self> {
+    Ok(Self {
+      regular: load_font(regular)?,
+      bold: load_font(bold)?,
+    })
+  }
+
+  pub fn render_png(&self, plan: &Plan) -> anyhow::Result<Vec<u8>> {
+    let content_width = WIDTH - 2.0 * PADDING;
+    let columns = HEADER.len();
+
+    let header = HEADER.map(|title| title.to_string());
+    let rows = std::iter::once(&header[..])
+      .chain(plan.rows.iter().map(Vec::as_slice))
+      .collect::<Vec<&[String]>>();
+
+    let widths = self.column_widths(&rows, columns, content_width);
+
+    let cells = rows
+      .iter()
+      .enumerate()
+      .map(|(index, row)| {
+        let font = if index == 0 {
+          &self.bold
+        } else {
+          &self.regular
+        };
+        (0..columns)
+          .map(|column| Cell {
+            lines: wrap(
+              font,
+              FONT_SIZE,
+              row.get(column).map(String::as_str).unwrap_or(""),
+              widths[column] - 2.0 * CELL_PADDING_X,
+            )
+            .into_iter()
+            .map(|text| Line { text, font })
+            .collect(),
+          })
+          .collect::<Vec<Cell>>()
+      })
+      .collect::<Vec<Vec<Cell>>>();
+
+    let cell_line_height = line_height(&self.regular, FONT_SIZE);
+    let row_heights = cells
+      .iter()
+      .map(|row| {
+        let lines = row.iter().map(|cell| cell.lines.len()).max().unwrap_or(0);
+        lines.max(1) as f32 * cell_line_height + 2.0 * CELL_PADDING_Y
+      })
+      .collect::<Vec<f32>>();
+
+    let title = format!(
+      "{} der {:0>2}. {} {}",
+      plan.date.weekday(),
+      plan.date.day(),
+      plan.date.month(),
+      plan.date.year()
+    );
+    let title_lines = wrap(&self.bold, HEADING_SIZE, &title, content_width);
+    let title_line_height = line_height(&self.bold, HEADING_SIZE);
+
+    let table_top = PADDING + title_lines.len() as f32 * title_line_height + HEADING_MARGIN;
+    let height = table_top + row_heights.iter().sum::<f32>() + PADDING;
+
+    let mut pixmap = Pixmap::new(WIDTH as u32, height.ceil() as u32)
+      .ok_or_else(|| anyhow::anyhow!("Invalid image size {}x{}", WIDTH, height))?;
+    pixmap.fill(color(BACKGROUND));
+
+    let mut y = PADDING;
+    for line in &title_lines {
+      draw_text(
+        &mut pixmap,
+        &self.bold,
+        HEADING_SIZE,
+        line,
+        PADDING,
+        y,
+        TEXT,
+      );
+      y += title_line_height;
+    }
+
+    let selected = selected_rows(&plan.rows, &plan.classes);
+
+    y = table_top;
+    for (index, (row, row_height)) in cells.iter().zip(&row_heights).enumerate() {
+      // the header is the first child of the table
+      let even = index % 2 == 1;
+      let (background, foreground) = match index {
+        0 => (HEADER_BACKGROUND, HEADER_TEXT),
+        _ if selected[index - 1] && even => (SELECTED_EVEN, TEXT),
+        _ if selected[index - 1] => (SELECTED_ODD, TEXT),
+        _ if even => (EVEN, TEXT),
+        _ => (ODD, TEXT),
+      };
+
+      fill_rect(
+        &mut pixmap,
+        PADDING,
+        y,
+        content_width,
+        *row_height,
+        background,
+      );
+
+      let mut x = PADDING;
+      for (cell, width) in row.iter().zip(&widths) {
+        self.draw_cell(&mut pixmap, cell, (x, y), (*width, *row_height), foreground);
+        x += width;
+      }
+
+      y += row_height;
+    }
+
+    Ok(pixmap.encode_png()?)
+  }
+
+  pub fn render_week_png(&self, week: &WeekPlan) -> anyhow::Result<Vec<u8>> {
+    let content_width = WIDTH - 2.0 * PADDING;
+    let lessons = week
+      .days
+      .iter()
+      .map(|day| day.cells.len())
+      .max()
+      .unwrap_or(0);
+
+    // the lesson column fits its header, the days share the remaining width
+    let lesson_width = text_width(&self.bold, FONT_SIZE, "Std.") + 2.0 * CELL_PADDING_X;
+    let day_width = (content_width - lesson_width) / week.days.len().max(1) as f32;
+    let widths = std::iter::once(lesson_width)
+      .chain(week.days.iter().map(|_| day_width))
+      .collect::<Vec<f32>>();
+
+    let cell = |font, text: &str, width: f32| Cell {
+      lines: wrap(font, FONT_SIZE, text, width - 2.0 * CELL_PADDING_X)
+        .into_iter()
+        .map(|text| Line { text, font })
+        .collect(),
+    };
+
+    // the header and one row per lesson, each with the cells and whether they changed
+    let mut rows = vec![std::iter::once(cell(&self.bold, "Std.", lesson_width))
+      .chain(
+        week
+          .days
+          .iter()
+          .map(|day| cell(&self.bold, &day.title, day_width)),
+      )
+      .map(|cell| (cell, false))
+      .collect::<Vec<(Cell, bool)>>()];
+    for lesson in 0..lessons {
+      let mut row = vec![(
+        cell(&self.regular, &(lesson + 1).to_string(), lesson_width),
+        false,
+      )];
+      for day in &week.days {
+        let (lines, changed) = match day.cells.get(lesson) {
+          Some(week_cell) => (
+            week_cell
+              .lines
+              .iter()
+              .flat_map(|line| cell(&self.regular, line, day_width).lines)
+              .collect(),
+            week_cell.changed,
+          ),
+          None => (Vec::new(), false),
+        };
+        row.push((Cell { lines }, changed));
+      }
+      rows.push(row);
+    }
+
+    let cell_line_height = line_height(&self.regular, FONT_SIZE);
+    let row_heights = rows
+      .iter()
+      .map(|row| {
+        let lines = row
+          .iter()
+          .map(|(cell, _)| cell.lines.len())
+          .max()
+          .unwrap_or(0);
+        lines.max(1) as f32 * cell_line_height + 2.0 * CELL_PADDING_Y
+      })
+      .collect::<Vec<f32>>();
+
+    let title = format!(
+      "Woche von {} ab {:0>2}.{:0>2}.{}",
+      week.class,
+      week.monday.day(),
+      week.monday.month() as u8,
+      week.monday.year()
+    );
+    let title_lines = wrap(&self.bold, HEADING_SIZE, &title, content_width);
+    let title_line_height = line_height(&self.bold, HEADING_SIZE);
+
+    let table_top = PADDING + title_lines.len() as f32 * title_line_height + HEADING_MARGIN;
+    let height = table_top + row_heights.iter().sum::<f32>() + PADDING;
+
+    let mut pixmap = Pixmap::new(WIDTH as u32, height.ceil() as u32)
+      .ok_or_else(|| anyhow::anyhow!("Invalid image size {}x{}", WIDTH, height))?;
+    pixmap.fill(color(BACKGROUND));
+
+    let mut y = PADDING;
+    for line in &title_lines {
+      draw_text(
+        &mut pixmap,
+        &self.bold,
+        HEADING_SIZE,
+        line,
+        PADDING,
+        y,
+        TEXT,
+      );
+      y += title_line_height;
+    }
+
+    y = table_top;
+    for (index, (row, row_height)) in rows.iter().zip(&row_heights).enumerate() {
+      let even = index % 2 == 1;
+
+      let mut x = PADDING;
+      for ((cell, changed), width) in row.iter().zip(&widths) {
+        let (background, foreground) = match index {
+          0 => (HEADER_BACKGROUND, HEADER_TEXT),
+          _ if *changed && even => (SELECTED_EVEN, TEXT),
+          _ if *changed => (SELECTED_ODD, TEXT),
+          _ if even => (EVEN, TEXT),
+          _ => (ODD, TEXT),
+        };
+
+        fill_rect(&mut pixmap, x, y, *width, *row_height, background);
+        self.draw_cell(&mut pixmap, cell, (x, y), (*width, *row_height), foreground);
+        x += width;
+      }
+
+      y += row_height;
+    }
+
+    Ok(pixmap.encode_png()?)
+  }
+
+  /// Draws the lines of a cell centered vertically and horizontally.
+  fn draw_cell(
+    &self,
+    pixmap: &mut Pixmap,
+    cell: &Cell,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    color: [u8; 3],
+  ) {
+    let cell_line_height = line_height(&self.regular, FONT_SIZE);
+    let text_height = cell.lines.len() as f32 * cell_line_height;
+    let mut line_y = y + (height - text_height) / 2.0;
+
+    for line in &cell.lines {
+      let line_width = text_width(line.font, FONT_SIZE, &line.text);
+      draw_text(
+        pixmap,
+        line.font,
+        FONT_SIZE,
+        &line.text,
+        x + (width - line_width) / 2.0,
+        line_y,
+        color,
+      );
+      line_y += cell_line_height;
+    }
+  }
+
+  /// Approximation of the automatic table layout of browsers: columns get
+  /// the width of their content, if there is not enough space the columns
+  /// are shrunk proportionally down to the width of their longest word.
+  fn column_widths(&self, rows: &[&[String]], columns: usize, available: f32) -> Vec<f32> {
+    let mut min = vec![0.0f32; columns];
+    let mut max = vec![0.0f32; columns];
+
+    for (index, row) in rows.iter().enumerate() {
+      let font = if index == 0 {
+        &self.bold
+      } else {
+        &self.regular
+      };
+
+      for column in 0..columns {
+        let text = row.get(column).map(String::as_str).unwrap_or("");
+        let longest_word = text
+          .split_whitespace()
+          .map(|word| text_width(font, FONT_SIZE, word))
+          .fold(0.0, f32::max);
+
+        min[column] = min[column].max(longest_word + 2.0 * CELL_PADDING_X);
+        max[column] = max[column].max(text_width(font, FONT_SIZE, text) + 2.0 * CELL_PADDING_X);
+      }
+    }
+
+    let min_total = min.iter().sum::<f32>();
+    let max_total = max.iter().sum::<f32>();
+
+    if max_total <= available {
+      let extra = available - max_total;
+      max
+        .iter()
+        .map(|width| width + extra * width / max_total)
+        .collect()
+    } else if min_total < available {
+      let extra = available - min_total;
+      min
+        .iter()
+        .zip(&max)
+        .map(|(min, max)| min + extra * (max - min) / (max_total - min_total))
+        .collect()
+    } else {
+      min
+    }
+  }
+}
+
+#[async_trait]
+impl ImageRenderer for NativeRenderer {
+  async fn render(&self, plan: &Plan) -> anyhow::Result<Vec<u8>> {
+    self.render_png(plan)
+  }
+
+  async fn render_week(&self, week: &WeekPlan) -> anyhow::Result<Vec<u8>> {
+    self.render_week_png(week)
+  }
+}
+
+fn load_font(data: &[u8]) -> anyhow::Result<FontVec> {
+  let data = if data.starts_with(b"wOF2") {
+    woff2_patched::convert_woff2_to_ttf(&mut &data[..])?
+  } else {
+    data.to_vec()
+  };
+
+  Ok(FontVec::try_from_vec(data)?)
+}
+
+/// Rows without class continue the class of the previous row, see `plan.stpl`.
+pub(crate) fn selected_rows(rows: &[Vec<String>], classes: &[String]) -> Vec<bool> {
+  let mut current = "";
+
+  rows
+    .iter()
+    .map(|row| {
+      if let Some(class) = row.first().filter(|class| !class.is_empty()) {
+        current = class;
+      }
+      classes.iter().any(|class| current.contains(class.as_str()))
+    })
+    .collect()
+}
+
+/// Converts the css font size to the scale used by ab_glyph.
+fn scale(font: &FontVec, size: f32) -> PxScale {
+  let units_per_em = font.units_per_em().unwrap_or(1000.0);
+  PxScale::from(size * font.height_unscaled() / units_per_em)
+}
+
+fn line_height(font: &FontVec, size: f32) -> f32 {
+  let font = font.as_scaled(scale(font, size));
+  font.height() + font.line_gap()
+}
+
+fn text_width(font: &FontVec, size: f32, text: &str) -> f32 {
+  let font = font.as_scaled(scale(font, size));
+  let mut width = 0.0;
+  let mut previous: Option<GlyphId> = None;
+
+  for c in text.chars() {
+    let id = font.glyph_id(c);
+    if let Some(previous) = previous {
+      width += font.kern(previous, id);
+    }
+    width += font.h_advance(id);
+    previous = Some(id);
+  }
+
+  width
+}
+
+/// Breaks `text` into lines fitting into `width`, words longer than a line are not split.
+pub(crate) fn wrap(font: &FontVec, size: f32, text: &str, width: f32) -> Vec<String> {
+  let mut lines = Vec::new();
+  let mut line = String::new();
+
+  for word in text.split_whitespace() {
+    if line.is_empty() {
+      line.push_str(word);
+      continue;
+    }
+
+    let candidate = format!("{line} {word}");
+    if text_width(font, size, &candidate) <= width {
+      line = candidate;
+    } else {
+      lines.push(std::mem::replace(&mut line, word.to_string()));
+    }
+  }
+
+  if !line.is_empty() {
+    lines.push(line);
+  }
+
+  lines
+}
+
+fn draw_text(
+  pixmap: &mut Pixmap,
+  font: &FontVec,
+  size: f32,
+  text: &str,
+  x: f32,
+  top: f32,
+  color: [u8; 3],
+) {
+  let font = font.as_scaled(scale(font, size));
+  let baseline = top + font.line_gap() / 2.0 + font.ascent();
+  let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+  let pixels = pixmap.pixels_mut();
+
+  let mut caret = x;
+  let mut previous: Option<GlyphId> = None;
+
+  for c in text.chars() {
+    let id = font.glyph_id(c);
+    if let Some(previous) = previous {
+      caret += font.kern(previous, id);
+    }
+
+    let glyph = id.with_scale_and_position(font.scale(), point(caret, baseline));
+    caret += font.h_advance(id);
+    previous = Some(id);
+
+    let Some(outlined) = font.outline_glyph(glyph) else {
+      continue;
+    };
+    let bounds = outlined.px_bounds();
+
+    outlined.draw(|glyph_x, glyph_y, coverage| {
+      let px = bounds.min.x as i32 + glyph_x as i32;
+      let py = bounds.min.y as i32 + glyph_y as i32;
+      if px < 0 || py < 0 || px >= width || py >= height {
+        return;
+      }
+
+      // the background is always opaque, blending can ignore its alpha
+      let pixel = &mut pixels[(py * width + px) as usize];
+      let coverage = coverage.clamp(0.0, 1.0);
+      let blend = |foreground: u8, background: u8| {
+        (foreground as f32 * coverage + background as f32 * (1.0 - coverage)).round() as u8
+      };
+
+      if let Some(blended) = PremultipliedColorU8::from_rgba(
+        blend(color[0], pixel.red()),
+        blend(color[1], pixel.green()),
+        blend(color[2], pixel.blue()),
+        255,
+      ) {
+        *pixel = blended;
+      }
+    });
+  }
+}
+
+fn fill_rect(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32, rgb: [u8; 3]) {
+  if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+    let mut paint = Paint::default();
+    paint.set_color(color(rgb));
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+  }
+}
+
+fn color(rgb: [u8; 3]) -> Color {
+  Color::from_rgba8(rgb[0], rgb[1], rgb[2], 255)
+}
+
+#[cfg(test)]
+mod test {
+  use time::{Date, Month};
+
+  use crate::native::{load_font, selected_rows, wrap, NativeRenderer};
+  use crate::{Plan, WeekCell, WeekDay, WeekPlan};
+
+  const REGULAR: &[u8] = include_bytes!("../../bszet-mind/static/Inter-Regular.woff2");
+  const BOLD: &[u8] = include_bytes!("../../bszet-mind/static/Inter-Bold.woff2");
+
+  fn row(columns: &[&str]) -> Vec<String> {
+    columns.iter().map(|column| column.to_string()).collect()
+  }
+
+  #[test]
+  fn test_selected_rows() {
+    let rows = vec![
+      row(&["IGD 21", "1", "Ma"]),
+      row(&["", "2", "De"]),
+      row(&["IGD22", "1", "Ma"]),
+      row(&["", "3", "En"]),
+      row(&["IGD21, IGD22", "4", "Ph"]),
+    ];
+
+    assert_eq!(
+      vec![true, true, false, false, true],
+      selected_rows(&rows, &["IGD21".to_string(), "IGD 21".to_string()])
+    );
+  }
+
+  #[test]
+  fn test_wrap() -> anyhow::Result<()> {
+    let font = load_font(REGULAR)?;
+
+    assert_eq!(
+      vec!["Raumänderung"],
+      wrap(&font, 38.0, "Raumänderung", 10.0)
+    );
+    assert_eq!(vec!["Raum", "B5"], wrap(&font, 38.0, "Raum  B5", 100.0));
+    assert_eq!(vec!["Raum B5"], wrap(&font, 38.0, "Raum B5", 1000.0));
+    assert!(wrap(&font, 38.0, "", 1000.0).is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render() -> anyhow::Result<()> {
+    let renderer = NativeRenderer::new(REGULAR, BOLD)?;
+
+    let image = renderer.render_png(&Plan {
+      date: Date::from_calendar_date(2023, Month::September, 4)?,
+      rows: vec![
+        row(&["IGD 21", "1", "Ma", "B11", "Mül", "Vertretung", ""]),
+        row(&[
+          "",
+          "2",
+          "De",
+          "B6",
+          "",
+          "Ausfall",
+          "Aufgaben im Lernraum bearbeiten",
+        ]),
+        row(&["IGD22", "3", "En", "B5", "Sch", "Raumänderung", ""]),
+      ],
+      classes: vec!["IGD21".to_string(), "IGD 21".to_string()],
+    })?;
+
+    assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+    // width of the IHDR chunk
+    assert_eq!(1500u32.to_be_bytes(), image[16..20]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_week() -> anyhow::Result<()> {
+    let renderer = NativeRenderer::new(REGULAR, BOLD)?;
+    let cell = |lines: &[&str], changed| WeekCell {
+      lines: lines.iter().map(|line| line.to_string()).collect(),
+      changed,
+    };
+
+    let image = renderer.render_week_png(&WeekPlan {
+      class: "IGD21".to_string(),
+      monday: Date::from_calendar_date(2023, Month::September, 4)?,
+      courses: String::new(),
+      days: vec![
+        WeekDay {
+          title: "Monday 04.09.".to_string(),
+          cells: vec![
+            cell(&["(D) B6", "Aufgaben im Lernraum"], true),
+            cell(&["Ch B11"], false),
+          ],
+        },
+        WeekDay {
+          title: "Tuesday 05.09.".to_string(),
+          cells: vec![cell(&["Ph B112", "En A102"], false)],
+        },
+        WeekDay {
+          title: "Wednesday 06.09.".to_string(),
+          cells: vec![],
+        },
+      ],
+    })?;
+
+    assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(1500u32.to_be_bytes(), image[16..20]);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,304 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::extract::{Path, Query};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Extension;
+use serde::Deserialize;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset, Weekday};
+use time_tz::Tz;
+
+use bszet_davinci::courses::Courses;
+use bszet_davinci::iteration::IterationCalendar;
+use bszet_davinci::timetable::{Lesson, Subject};
+use bszet_davinci::Davinci;
+
+use crate::api::AppError;
+use crate::bells::Bells;
+use crate::schedule;
+
+const UTC_FORMAT: &[FormatItem] = format_description!("[year][month][day]T[hour][minute][second]Z");
+const DATE_FORMAT: &[FormatItem] = format_description!("[year][month][day]");
+
+const MAX_DAYS: usize = 60;
+
+#[derive(Deserialize)]
+pub(crate) struct CalendarQuery {
+  #[serde(default = "default_days")]
+  days: usize,
+  #[serde(default)]
+  courses: String,
+}
+
+fn default_days() -> usize {
+  14
+}
+
+/// Feed of a class as `<class>:<secret>`, calendar apps can not send the API token
+/// and subscribe to `/calendar/<secret>/<class>.ics` instead.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Feed {
+  pub(crate) class: String,
+  secret: String,
+}
+
+impl Feed {
+  /// Compares the secret in constant time.
+  pub(crate) fn authorizes(&self, class: &str, secret: &str) -> bool {
+    self.class == class
+      && self.secret.len() == secret.len()
+      && self
+        .secret
+        .bytes()
+        .zip(secret.bytes())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+  }
+}
+
+impl FromStr for Feed {
+  type Err = anyhow::Error;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let (class, secret) = value
+      .split_once(':')
+      .ok_or_else(|| anyhow!("Feed {value:?} is missing its secret, expected <class>:<secret>"))?;
+    let secret = secret.trim();
+    if secret.len() < 16 {
+      return Err(anyhow!(
+        "Secret of the feed of {} is shorter than 16 characters",
+        class.trim()
+      ));
+    }
+
+    Ok(Self {
+      class: class.trim().to_string(),
+      secret: secret.to_string(),
+    })
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Event {
+  pub(crate) uid: String,
+  pub(crate) start: OffsetDateTime,
+  pub(crate) end: OffsetDateTime,
+  pub(crate) summary: String,
+  pub(crate) location: Option<String>,
+  pub(crate) description: Option<String>,
+  pub(crate) cancelled: bool,
+}
+
+/// Applied timetable of the next school days as iCalendar, like `/calendar/<secret>/IGD21.ics`.
+pub(crate) async fn calendar(
+  Extension(davinci): Extension<Arc<Davinci>>,
+  Extension(timezone): Extension<&'static Tz>,
+  Extension(bells): Extension<Arc<Bells>>,
+  Extension(feeds): Extension<Arc<Vec<Feed>>>,
+  Path((secret, file)): Path<(String, String)>,
+  Query(CalendarQuery { days, courses }): Query<CalendarQuery>,
+) -> Result<impl IntoResponse, AppError> {
+  // unknown classes and wrong secrets are not told apart
+  let class = file
+    .strip_suffix(".ics")
+    .and_then(|class| davinci.timetables().get(class))
+    .map(|(class, _)| class.name.clone())
+    .filter(|class| feeds.iter().any(|feed| feed.authorizes(class, &secret)))
+    .ok_or(AppError::UnknownClass)?;
+  let courses = Courses::parse(&courses, davinci.timetables().subjects())
+    .map_err(|_| AppError::UnknownCourse)?;
+
+  let mut events = Vec::new();
+  for date in school_days(
+    davinci.calendar(),
+    schedule::now(timezone).date(),
+    days.min(MAX_DAYS),
+  ) {
+    let (_, lessons, _, _) = davinci
+      .get_applied_timetable(&class, date, &courses)
+      .await?;
+    events.extend(lesson_events(&class, date, &lessons, &bells, timezone));
+  }
+
+  Ok((
+    [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+    render(&class, &events, OffsetDateTime::now_utc()),
+  ))
+}
+
+/// The next `count` days from `from` on which a timetable applies.
+pub(crate) fn school_days(calendar: &IterationCalendar, from: Date, count: usize) -> Vec<Date> {
+  let mut days = Vec::with_capacity(count);
+  let mut date = from;
+
+  // summer holidays are the longest span without school days
+  for _ in 0..366 {
+    if days.len() == count {
+      break;
+    }
+    if !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday)
+      && !calendar.is_holiday(date)
+      && calendar.get_iteration(date).is_some()
+    {
+      days.push(date);
+    }
+    date += Duration::days(1);
+  }
+
+  days
+}
+
+/// Events of the lessons of a day, lessons without bells are left out.
+pub(crate) fn lesson_events(
+  class: &str,
+  date: Date,
+  lessons: &[Lesson],
+  bells: &Bells,
+  timezone: &Tz,
+) -> Vec<Event> {
+  lessons
+    .iter()
+    .filter_map(|lesson| {
+      let bell = bells.get(date.weekday(), lesson.lesson)?;
+
+      let (subject, cancelled) = match &lesson.subject {
+        Subject::Cancel(subject) => (subject.as_ref(), true),
+        subject => (subject, false),
+      };
+
+      let mut description = Vec::new();
+      if !lesson.teachers.is_empty() {
+        description.push(lesson.teachers.join(", "));
+      }
+      if let Some(notice) = &lesson.notice {
+        description.push(notice.clone());
+      }
+
+      Some(Event {
+        uid: uid(class, date, lesson, subject),
+        start: schedule::local(PrimitiveDateTime::new(date, bell.from), timezone),
+        end: schedule::local(PrimitiveDateTime::new(date, bell.to), timezone),
+        summary: subject
+          .info()
+          .map(|info| info.name.clone())
+          .unwrap_or_else(|| subject.to_string()),
+        location: lesson.place.clone(),
+        description: (!description.is_empty()).then(|| description.join("\n")),
+        cancelled,
+      })
+    })
+    .collect()
+}
+
+/// Identifies the lesson of the timetable independent of its changes, so clients update the
+/// event in place. Added lessons are told apart by their own subject.
+fn uid(class: &str, date: Date, lesson: &Lesson, subject: &Subject) -> String {
+  let (added, subject) = match &lesson.planned {
+    Some(planned) => ("", planned),
+    None => ("added-", subject),
+  };
+  let subject = match subject {
+    Subject::Known(info) => info.id.as_str(),
+    Subject::Other(other) => other.as_str(),
+    _ => "None",
+  };
+
+  let mut uid = format!(
+    "{}-{}-{}-{}{}",
+    date.format(DATE_FORMAT).unwrap(),
+    class,
+    lesson.lesson,
+    added,
+    subject
+  );
+  if let Some(group) = &lesson.group {
+    uid.push('-');
+    uid.push_str(group);
+  }
+
+  let uid = uid
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+    .collect::<String>();
+  format!("{uid}@bszet-mind")
+}
+
+pub(crate) fn render(class: &str, events: &[Event], stamp: OffsetDateTime) -> String {
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    "PRODID:-//bszet-mind//Stundenplan//DE".to_string(),
+    "CALSCALE:GREGORIAN".to_string(),
+    "METHOD:PUBLISH".to_string(),
+    format!("X-WR-CALNAME:{}", escape(&format!("Stundenplan {class}"))),
+  ];
+
+  for event in events {
+    lines.push("BEGIN:VEVENT".to_string());
+    lines.push(format!("UID:{}", event.uid));
+    lines.push(format!("DTSTAMP:{}", utc(stamp)));
+    lines.push(format!("DTSTART:{}", utc(event.start)));
+    lines.push(format!("DTEND:{}", utc(event.end)));
+    lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+    if let Some(location) = &event.location {
+      lines.push(format!("LOCATION:{}", escape(location)));
+    }
+    if let Some(description) = &event.description {
+      lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+    lines.push(
+      if event.cancelled {
+        "STATUS:CANCELLED"
+      } else {
+        "STATUS:CONFIRMED"
+      }
+      .to_string(),
+    );
+    lines.push("END:VEVENT".to_string());
+  }
+
+  lines.push("END:VCALENDAR".to_string());
+
+  lines
+    .iter()
+    .map(|line| fold(line))
+    .collect::<Vec<String>>()
+    .join("\r\n")
+    + "\r\n"
+}
+
+fn utc(date_time: OffsetDateTime) -> String {
+  date_time
+    .to_offset(UtcOffset::UTC)
+    .format(UTC_FORMAT)
+    .unwrap()
+}
+
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace('\n', "\\n")
+}
+
+/// Splits lines longer than 75 octets, continuations start with a space.
+fn fold(line: &str) -> String {
+  let mut folded = String::with_capacity(line.len());
+  let mut length = 0;
+
+  for c in line.chars() {
+    if length + c.len_utf8() > 75 {
+      folded.push_str("\r\n ");
+      length = 1;
+    }
+    folded.push(c);
+    length += c.len_utf8();
+  }
+
+  folded
+}
@@ -3,14 +3,17 @@ use crate::api::AppError::PlanUnavailable;
 use axum::extract::{Path, Query};
 use axum::response::{Html, IntoResponse};
 use axum::{Extension, Json};
-use bszet_davinci::timetable::Subject;
-use bszet_davinci::Davinci;
-use serde::{Deserialize, Serialize};
+use bszet_davinci::courses::Courses;
+use bszet_davinci::subjects::SubjectDictionary;
+use bszet_davinci::timetable::{self, Subject};
+use bszet_davinci::{Davinci, Row};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::sync::Arc;
 use time::serde::format_description;
-use time::Date;
+use time::{Date, Weekday};
 
-format_description!(iso_date, Date, "[year]-[month]-[day]");
+format_description!(pub(crate) iso_date, Date, "[year]-[month]-[day]");
 
 #[derive(Deserialize)]
 pub(crate) struct PlanPath {
@@ -28,7 +31,15 @@ pub(crate) async fn html_plan(
   Path(PlanPath { date }): Path<PlanPath>,
   Query(PlanQuery { class }): Query<PlanQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-  let split = class.split(',').collect::<Vec<&str>>();
+  // expand configured classes to all of their spellings
+  let classes = davinci.timetables().classes();
+  let split = class
+    .split(',')
+    .flat_map(|class| match classes.resolve(class) {
+      Some(class) => class.spellings().collect::<Vec<&str>>(),
+      None => vec![class],
+    })
+    .collect::<Vec<&str>>();
   Ok(Html(
     davinci
       .get_html(&date, split.as_slice())
@@ -44,42 +55,205 @@ pub(crate) struct TimetablePath {
   class: String,
 }
 
+/// Selected courses like `?courses=LK-Ma,En,Gruppe 2`, all lessons without.
+#[derive(Deserialize)]
+pub(crate) struct TimetableQuery {
+  #[serde(default)]
+  courses: String,
+}
+
+impl TimetableQuery {
+  pub(crate) fn courses(&self, subjects: &SubjectDictionary) -> Result<Courses, AppError> {
+    Courses::parse(&self.courses, subjects).map_err(|_| AppError::UnknownCourse)
+  }
+}
+
 #[derive(Clone, Debug, Serialize)]
 struct Lesson {
   pub lesson: u8,
   pub subject: String,
+  pub name: Option<String>,
+  pub color: Option<String>,
   pub iteration: Option<u8>,
   pub place: Option<String>,
+  pub teachers: Vec<String>,
   pub notice: Option<String>,
   pub cancel: bool,
 }
 
 pub(crate) async fn timetable(
   Extension(davinci): Extension<Arc<Davinci>>,
-  Path(TimetablePath { date, .. }): Path<TimetablePath>,
+  Path(TimetablePath { date, class }): Path<TimetablePath>,
+  Query(query): Query<TimetableQuery>,
 ) -> Result<impl IntoResponse, AppError> {
+  if davinci.timetables().get(&class).is_none() {
+    return Err(AppError::UnknownClass);
+  }
+  let courses = query.courses(davinci.timetables().subjects())?;
+
   Ok(Json(
     davinci
-      .get_applied_timetable(date)
+      .get_applied_timetable(&class, date, &courses)
       .await
       .map_err(|_| AppError::IterationNotAvailable)?
       .1
       .into_iter()
-      .map(|lesson| {
-        let (subject, cancel) = match lesson.subject {
-          Subject::Cancel(subject) => (*subject, true),
-          subject => (subject, false),
-        };
-
-        Lesson {
-          lesson: lesson.lesson,
-          subject: format!("{subject}"),
-          iteration: lesson.iteration,
-          place: lesson.place,
-          notice: lesson.notice,
-          cancel,
-        }
-      })
+      .map(Lesson::from)
       .collect::<Vec<Lesson>>(),
   ))
 }
+
+impl From<timetable::Lesson> for Lesson {
+  fn from(lesson: timetable::Lesson) -> Self {
+    let (subject, cancel) = match lesson.subject {
+      Subject::Cancel(subject) => (*subject, true),
+      subject => (subject, false),
+    };
+
+    let info = subject.info();
+
+    Self {
+      lesson: lesson.lesson,
+      subject: format!("{subject}"),
+      name: info.map(|info| info.name.clone()),
+      color: info.and_then(|info| info.color.clone()),
+      iteration: lesson.iteration,
+      place: lesson.place,
+      teachers: lesson.teachers,
+      notice: lesson.notice,
+      cancel,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct WeekPath {
+  #[serde(deserialize_with = "iso_week")]
+  week: Date,
+  class: String,
+}
+
+/// Monday of an ISO week like `2023-W36`.
+pub(crate) fn parse_iso_week(value: &str) -> Option<Date> {
+  let (year, week) = value.split_once(['W', 'w'])?;
+  let year = year.strip_suffix('-').unwrap_or(year).parse().ok()?;
+
+  Date::from_iso_week_date(year, week.parse().ok()?, Weekday::Monday).ok()
+}
+
+fn iso_week<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+  let value = String::deserialize(deserializer)?;
+  parse_iso_week(&value).ok_or_else(|| D::Error::custom(format!("invalid iso week {value:?}")))
+}
+
+#[derive(Serialize)]
+struct Week {
+  #[serde(with = "iso_date")]
+  monday: Date,
+  days: Vec<Day>,
+  /// Rows of the week which could not be applied
+  unapplied: Vec<ChangedRow>,
+}
+
+#[derive(Serialize)]
+struct Day {
+  #[serde(with = "iso_date")]
+  date: Date,
+  /// No school without iteration
+  iteration: Option<u8>,
+  lessons: Vec<Lesson>,
+}
+
+pub(crate) async fn week(
+  Extension(davinci): Extension<Arc<Davinci>>,
+  Path(WeekPath { week, class }): Path<WeekPath>,
+  Query(query): Query<TimetableQuery>,
+) -> Result<impl IntoResponse, AppError> {
+  if davinci.timetables().get(&class).is_none() {
+    return Err(AppError::UnknownClass);
+  }
+  let courses = query.courses(davinci.timetables().subjects())?;
+
+  let (_, days, unapplied) = davinci.get_applied_week(&class, week, &courses).await?;
+
+  Ok(Json(Week {
+    monday: week,
+    days: days
+      .into_iter()
+      .map(|day| Day {
+        date: day.date,
+        iteration: day.iteration,
+        lessons: day.lessons.into_iter().map(Lesson::from).collect(),
+      })
+      .collect(),
+    unapplied: unapplied.iter().map(ChangedRow::from).collect(),
+  }))
+}
+
+pub(crate) async fn html_week(
+  Extension(davinci): Extension<Arc<Davinci>>,
+  Path(WeekPath { week, class }): Path<WeekPath>,
+  Query(query): Query<TimetableQuery>,
+) -> Result<impl IntoResponse, AppError> {
+  if davinci.timetables().get(&class).is_none() {
+    return Err(AppError::UnknownClass);
+  }
+  let courses = query.courses(davinci.timetables().subjects())?;
+
+  Ok(Html(davinci.get_week_html(&class, week, &courses).await?))
+}
+
+#[derive(Serialize)]
+struct Changes {
+  added: Vec<ChangedRow>,
+  removed: Vec<ChangedRow>,
+  modified: Vec<ModifiedRow>,
+}
+
+#[derive(Serialize)]
+struct ChangedRow {
+  #[serde(with = "iso_date")]
+  date: Date,
+  class: Vec<String>,
+  change: String,
+}
+
+#[derive(Serialize)]
+struct ModifiedRow {
+  before: ChangedRow,
+  after: ChangedRow,
+}
+
+impl From<&Row> for ChangedRow {
+  fn from(row: &Row) -> Self {
+    Self {
+      date: row.date,
+      class: row.class.clone(),
+      change: row.change.to_string(),
+    }
+  }
+}
+
+pub(crate) async fn changes(
+  Extension(davinci): Extension<Arc<Davinci>>,
+) -> Result<impl IntoResponse, AppError> {
+  let data = davinci.data().await;
+  let diff = &data.as_ref().ok_or(PlanUnavailable)?.diff;
+
+  Ok(Json(Changes {
+    added: diff.added.iter().map(ChangedRow::from).collect(),
+    removed: diff.removed.iter().map(ChangedRow::from).collect(),
+    modified: diff
+      .modified
+      .iter()
+      .map(|modification| ModifiedRow {
+        before: (&modification.before).into(),
+        after: (&modification.after).into(),
+      })
+      .collect(),
+  }))
+}
+
+pub(crate) async fn stats(Extension(davinci): Extension<Arc<Davinci>>) -> impl IntoResponse {
+  Json(davinci.stats().await)
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use time::Date;
+use time_tz::Tz;
+
+use bszet_davinci::Davinci;
+
+use crate::api::davinci::iso_date;
+use crate::schedule;
+
+#[derive(Deserialize)]
+pub(crate) struct IterationsQuery {
+  #[serde(default, with = "iso_date::option")]
+  from: Option<Date>,
+  #[serde(default = "default_weeks")]
+  weeks: usize,
+}
+
+fn default_weeks() -> usize {
+  10
+}
+
+#[derive(Serialize)]
+struct Week {
+  #[serde(with = "iso_date")]
+  monday: Date,
+  iteration: Option<u8>,
+}
+
+pub(crate) async fn iterations(
+  Extension(davinci): Extension<Arc<Davinci>>,
+  Extension(timezone): Extension<&'static Tz>,
+  Query(IterationsQuery { from, weeks }): Query<IterationsQuery>,
+) -> impl IntoResponse {
+  let from = from.unwrap_or_else(|| schedule::now(timezone).date());
+
+  Json(
+    davinci
+      .calendar()
+      .weeks(from, weeks.min(52))
+      .into_iter()
+      .map(|week| Week {
+        monday: week.monday,
+        iteration: week.iteration,
+      })
+      .collect::<Vec<Week>>(),
+  )
+}
@@ -2,12 +2,16 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use tracing::error;
 
+pub(crate) mod calendar;
 pub(crate) mod davinci;
+pub(crate) mod iteration;
 
 pub(crate) enum AppError {
   InternalServerError(anyhow::Error),
   PlanUnavailable,
   IterationNotAvailable,
+  UnknownClass,
+  UnknownCourse,
 }
 
 impl From<anyhow::Error> for AppError {
@@ -31,6 +35,8 @@ impl IntoResponse for AppError {
         StatusCode::BAD_REQUEST,
         "iteration for given date not available",
       ),
+      AppError::UnknownClass => (StatusCode::NOT_FOUND, "unknown class"),
+      AppError::UnknownCourse => (StatusCode::BAD_REQUEST, "unknown course"),
     };
 
     (status, error_message).into_response()
@@ -2,62 +2,51 @@ use std::fmt::Write;
 
 use bszet_davinci::timetable::Lesson;
 
+/// Aligned columns of lesson, subject, place, teachers and notice, empty
+/// place and teacher columns are left out.
 pub fn table(day: Vec<Lesson>) -> String {
-  let mut lesson_w = 0;
-  let mut subject_w = 0;
-  let mut place_w = 0;
-
-  for lesson in &day {
-    let l = format!("{}", lesson.lesson);
-    let s = format!("{}", lesson.subject);
-    let p = &lesson.place;
-
-    lesson_w = lesson_w.max(l.chars().count());
-    subject_w = subject_w.max(s.chars().count());
-    place_w = place_w.max(p.as_ref().map(|s| s.chars().count()).unwrap_or(0));
+  let rows = day
+    .iter()
+    .map(|lesson| {
+      [
+        format!("{}", lesson.lesson),
+        format!("{}", lesson.subject),
+        lesson.place.clone().unwrap_or_default(),
+        lesson.teachers.join(", "),
+      ]
+    })
+    .collect::<Vec<[String; 4]>>();
+
+  let mut widths = [0; 4];
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
   }
 
   // only works with ascii characters, with utf like ü, ä, ö, ß, ...
   // there will be an additional allocation
-  let mut out = String::with_capacity(day.len() * (lesson_w + subject_w + place_w + 2));
-
-  let mut first = true;
+  let mut out = String::with_capacity(day.len() * (widths.iter().sum::<usize>() + 4));
 
-  for lesson in &day {
-    let l = format!("{}", lesson.lesson);
-    let s = format!("{}", lesson.subject);
-    let p = &lesson.place;
-
-    if first {
-      first = false;
-    } else {
+  for (i, (lesson, row)) in day.iter().zip(&rows).enumerate() {
+    if i > 0 {
       writeln!(out).unwrap();
     }
 
-    if let Some(notice) = &lesson.notice {
-      write!(
-        out,
-        "{}{} {}{} {}{} {}",
-        l,
-        " ".repeat(lesson_w - l.chars().count()),
-        s,
-        " ".repeat(subject_w - s.chars().count()),
-        p.as_ref().unwrap_or(&"".to_string()),
-        " ".repeat(place_w - p.as_ref().map(|s| s.chars().count()).unwrap_or(0)),
-        notice
-      )
-      .unwrap();
-    } else {
-      write!(
-        out,
-        "{}{} {}{} {}",
-        l,
-        " ".repeat(lesson_w - l.chars().count()),
-        s,
-        " ".repeat(subject_w - s.chars().count()),
-        p.as_ref().unwrap_or(&"".to_string())
-      )
-      .unwrap();
+    let mut line = String::new();
+    for (column, (width, cell)) in widths.iter().zip(row).enumerate() {
+      if *width == 0 {
+        continue;
+      }
+      if column > 0 {
+        line.push(' ');
+      }
+      write!(line, "{}{}", cell, " ".repeat(width - cell.chars().count())).unwrap();
+    }
+
+    match &lesson.notice {
+      Some(notice) => write!(out, "{line} {notice}").unwrap(),
+      None => out.push_str(line.trim_end()),
     }
   }
 
@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use time::macros::time;
+use time::{Time, Weekday};
+
+use bszet_davinci::format::load;
+use bszet_davinci::timetable::{Lesson, Subject};
+
+use crate::schedule::hh_mm;
+
+/// Start and end of a lesson in the timezone of the school.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Bell {
+  /// Number of the lesson as in the timetables
+  pub(crate) lesson: u8,
+  #[serde(with = "hh_mm")]
+  pub(crate) from: Time,
+  #[serde(with = "hh_mm")]
+  pub(crate) to: Time,
+}
+
+/// When lessons start and end, weekdays can override the times of single lessons.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Bells {
+  pub(crate) lessons: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) monday: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) tuesday: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) wednesday: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) thursday: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) friday: Vec<Bell>,
+  #[serde(default)]
+  pub(crate) saturday: Vec<Bell>,
+}
+
+impl Default for Bells {
+  /// The blocks of the BSZ ET.
+  fn default() -> Self {
+    let bell = |lesson, from, to| Bell { lesson, from, to };
+
+    Self {
+      lessons: vec![
+        bell(1, time!(7:30), time!(9:00)),
+        bell(2, time!(9:10), time!(10:40)),
+        bell(3, time!(11:10), time!(12:40)),
+        bell(4, time!(13:10), time!(14:40)),
+        bell(5, time!(14:50), time!(16:20)),
+      ],
+      monday: Vec::new(),
+      tuesday: Vec::new(),
+      wednesday: Vec::new(),
+      thursday: Vec::new(),
+      friday: Vec::new(),
+      saturday: Vec::new(),
+    }
+  }
+}
+
+impl Bells {
+  pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+    let bells = load::<Self>(path)?;
+    bells
+      .validate()
+      .map_err(|err| anyhow!("Invalid bells {}: {}", path.display(), err))?;
+    Ok(bells)
+  }
+
+  pub(crate) fn validate(&self) -> anyhow::Result<()> {
+    if self.lessons.is_empty() {
+      return Err(anyhow!("no lessons configured"));
+    }
+
+    for (day, bells) in [
+      ("lessons", &self.lessons),
+      ("monday", &self.monday),
+      ("tuesday", &self.tuesday),
+      ("wednesday", &self.wednesday),
+      ("thursday", &self.thursday),
+      ("friday", &self.friday),
+      ("saturday", &self.saturday),
+    ] {
+      for (index, bell) in bells.iter().enumerate() {
+        if bell.from >= bell.to {
+          return Err(anyhow!(
+            "lesson {} of {} ends before it starts",
+            bell.lesson,
+            day
+          ));
+        }
+        if bells[..index]
+          .iter()
+          .any(|other| other.lesson == bell.lesson)
+        {
+          return Err(anyhow!(
+            "lesson {} of {} is configured twice",
+            bell.lesson,
+            day
+          ));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn overrides(&self, weekday: Weekday) -> &[Bell] {
+    match weekday {
+      Weekday::Monday => &self.monday,
+      Weekday::Tuesday => &self.tuesday,
+      Weekday::Wednesday => &self.wednesday,
+      Weekday::Thursday => &self.thursday,
+      Weekday::Friday => &self.friday,
+      Weekday::Saturday => &self.saturday,
+      Weekday::Sunday => &[],
+    }
+  }
+
+  pub(crate) fn get(&self, weekday: Weekday, lesson: u8) -> Option<&Bell> {
+    self
+      .overrides(weekday)
+      .iter()
+      .chain(&self.lessons)
+      .find(|bell| bell.lesson == lesson)
+  }
+
+  /// End of the last lesson of the day, from then on the next school day is relevant.
+  pub(crate) fn end_of_day(&self, weekday: Weekday) -> Time {
+    self
+      .overrides(weekday)
+      .iter()
+      .chain(&self.lessons)
+      .filter(|bell| self.get(weekday, bell.lesson) == Some(bell))
+      .map(|bell| bell.to)
+      .max()
+      .unwrap()
+  }
+
+  /// Lessons starting next after `time`, parallel lessons start together.
+  pub(crate) fn next<'a>(
+    &self,
+    weekday: Weekday,
+    lessons: &'a [Lesson],
+    time: Time,
+  ) -> Option<(Time, Vec<&'a Lesson>)> {
+    let start = |lesson: &Lesson| {
+      self
+        .get(weekday, lesson.lesson)
+        .map(|bell| bell.from)
+        .filter(|from| *from > time)
+    };
+    let upcoming = lessons
+      .iter()
+      .filter(|lesson| !matches!(lesson.subject, Subject::Cancel(_)))
+      .filter_map(|lesson| Some((start(lesson)?, lesson)))
+      .collect::<Vec<(Time, &Lesson)>>();
+
+    let first = upcoming.iter().map(|(from, _)| *from).min()?;
+    Some((
+      first,
+      upcoming
+        .into_iter()
+        .filter(|(from, _)| *from == first)
+        .map(|(_, lesson)| lesson)
+        .collect(),
+    ))
+  }
+}
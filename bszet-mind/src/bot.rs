@@ -0,0 +1,328 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use time::{OffsetDateTime, PrimitiveDateTime, Weekday};
+use time_tz::Tz;
+use tracing::{error, info};
+
+use bszet_davinci::class::Class;
+use bszet_davinci::courses::Courses;
+use bszet_davinci::Davinci;
+use bszet_notify::telegram::{Message, Telegram};
+
+use crate::bells::Bells;
+use crate::message::escape;
+use crate::schedule::next_school_day;
+use crate::subscriptions::Subscriptions;
+use crate::{format_duration, message, schedule};
+
+const HELP: &str = "Verfügbare Befehle:
+/subscribe <Klasse> - Vertretungsplan einer Klasse abonnieren
+/unsubscribe [Klasse] - Abo einer oder aller Klassen beenden
+/today [Klasse] - Plan für heute
+/tomorrow [Klasse] - Plan für den nächsten Schultag
+/week [Klasse] - Plan für diese Woche
+/next [Klasse] - Nächste Stunde heute
+/courses [Kurse] - Belegte Kurse und Gruppe, wie \"LK-Ma, En, Ru, Gruppe 2\", oder \"alle\"
+/status - Stand des Vertretungsplans und Abos";
+
+const COURSES_EXAMPLE: &str = "bitte gib die Kurse wie in \"LK-Ma, En, Ru, Gruppe 2\" an.";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command<'a> {
+  Subscribe(Option<&'a str>),
+  Unsubscribe(Option<&'a str>),
+  Today(Option<&'a str>),
+  Tomorrow(Option<&'a str>),
+  Week(Option<&'a str>),
+  Next(Option<&'a str>),
+  Courses(Option<&'a str>),
+  Status,
+  Help,
+}
+
+impl<'a> Command<'a> {
+  /// Parses commands like `/subscribe IGD 21` or `/today@bot`, `None` for other messages.
+  pub(crate) fn parse(text: &'a str) -> Option<Self> {
+    let text = text.trim().strip_prefix('/')?;
+    let (command, argument) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let command = command.split('@').next().unwrap_or(command);
+    let argument = Some(argument.trim()).filter(|argument| !argument.is_empty());
+
+    Some(match command {
+      "subscribe" => Self::Subscribe(argument),
+      "unsubscribe" => Self::Unsubscribe(argument),
+      "today" => Self::Today(argument),
+      "tomorrow" => Self::Tomorrow(argument),
+      "week" => Self::Week(argument),
+      "next" => Self::Next(argument),
+      "courses" => Self::Courses(argument),
+      "status" => Self::Status,
+      _ => Self::Help,
+    })
+  }
+}
+
+/// Answers commands sent to the bot until the process is terminated.
+pub(crate) async fn run(
+  telegram: &Telegram,
+  davinci: &Davinci,
+  subscriptions: &Subscriptions,
+  bells: &Bells,
+  timezone: &Tz,
+) {
+  let mut poller = telegram.poller(30);
+
+  loop {
+    match poller.next().await {
+      Ok(messages) => {
+        for message in messages {
+          if let Err(err) =
+            handle(telegram, davinci, subscriptions, bells, timezone, &message).await
+          {
+            error!("Unable to handle telegram message: {}", err);
+          }
+        }
+      }
+      Err(err) => {
+        error!("Unable to receive telegram updates: {}", err);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+      }
+    }
+  }
+}
+
+async fn handle(
+  telegram: &Telegram,
+  davinci: &Davinci,
+  subscriptions: &Subscriptions,
+  bells: &Bells,
+  timezone: &Tz,
+  message: &Message,
+) -> anyhow::Result<()> {
+  let Some(command) = message.text.as_deref().and_then(Command::parse) else {
+    return Ok(());
+  };
+
+  let chat = message.chat.id;
+  info!("Received {:?} from chat {}", command, chat);
+
+  let reply = match command {
+    Command::Subscribe(None) => format!(
+      "Bitte gib eine Klasse an, verfügbar sind: {}",
+      escape(&available_classes(davinci))
+    ),
+    Command::Subscribe(Some(class)) => match davinci.timetables().classes().resolve(class) {
+      None => format!(
+        "Unbekannte Klasse {}, verfügbar sind: {}",
+        escape(class),
+        escape(&available_classes(davinci))
+      ),
+      Some(class) => {
+        if subscriptions.subscribe(chat, class.name.clone()).await? {
+          format!("Vertretungsplan von {} abonniert.", escape(&class.name))
+        } else {
+          format!("{} ist bereits abonniert.", escape(&class.name))
+        }
+      }
+    },
+    Command::Unsubscribe(class) => {
+      let class = class.map(|class| {
+        davinci
+          .timetables()
+          .classes()
+          .resolve(class)
+          .map(|class| class.name.as_str())
+          .unwrap_or(class)
+      });
+
+      let removed = subscriptions.unsubscribe(chat, class).await?;
+      if removed.is_empty() {
+        "Es gibt kein passendes Abo.".to_string()
+      } else {
+        format!("Abo beendet: {}", escape(&removed.join(", ")))
+      }
+    }
+    Command::Today(class)
+    | Command::Tomorrow(class)
+    | Command::Week(class)
+    | Command::Next(class) => {
+      let classes = classes(davinci, subscriptions, chat, class).await;
+      if classes.is_empty() {
+        "Keine Klasse abonniert, nutze /subscribe <Klasse>.".to_string()
+      } else {
+        let now = schedule::now(timezone);
+        let today = now.date();
+        let courses = subscriptions.courses(chat).await;
+        let mut texts = Vec::with_capacity(classes.len());
+
+        for class in classes {
+          texts.push(match command {
+            Command::Today(_) => {
+              message::plan(davinci, class, next_school_day(today), None, &courses)
+                .await
+                .unwrap_or_else(|err| no_plan(class, err))
+            }
+            Command::Tomorrow(_) => message::plan(
+              davinci,
+              class,
+              next_school_day(today + time::Duration::days(1)),
+              None,
+              &courses,
+            )
+            .await
+            .unwrap_or_else(|err| no_plan(class, err)),
+            Command::Next(_) => davinci
+              .get_applied_timetable(&class.name, today, &courses)
+              .await
+              .ok()
+              .and_then(|(_, day, _, _)| {
+                message::next_lesson(bells, &day, PrimitiveDateTime::new(today, now.time()))
+              })
+              .map(|next| format!("{}: {}", escape(&class.name), next))
+              .unwrap_or_else(|| format!("{}: Heute keine Stunden mehr.", escape(&class.name))),
+            _ => {
+              let monday = schedule::monday(next_school_day(today));
+              message::week(davinci, class, monday, &courses).await
+            }
+          });
+        }
+
+        texts.join("\n\n")
+      }
+    }
+    Command::Courses(None) => {
+      let courses = subscriptions.courses(chat).await;
+      if courses.is_empty() {
+        "Keine Kurse gewählt, es werden alle Stunden angezeigt.".to_string()
+      } else {
+        format!("Gewählte Kurse: {}", escape(&courses.to_string()))
+      }
+    }
+    Command::Courses(Some(selection)) => {
+      let courses = if selection.eq_ignore_ascii_case("alle") {
+        Ok((Courses::default(), Vec::new()))
+      } else {
+        Courses::parse_known(selection, davinci.timetables().subjects())
+      };
+
+      // the errors of the parser are english, replies are built from its results instead
+      match courses {
+        Err(_) => format!("Bitte wähle höchstens eine Gruppe, {COURSES_EXAMPLE}"),
+        Ok((_, unknown)) if !unknown.is_empty() => format!(
+          "Unbekannte Kurse: {}, {COURSES_EXAMPLE}",
+          escape(&unknown.join(", "))
+        ),
+        Ok((courses, _)) if courses.is_empty() => {
+          subscriptions.select_courses(chat, courses).await?;
+          "Es werden wieder alle Stunden angezeigt.".to_string()
+        }
+        Ok((courses, _)) => {
+          let text = format!("Gewählte Kurse: {}", escape(&courses.to_string()));
+          subscriptions.select_courses(chat, courses).await?;
+          text
+        }
+      }
+    }
+    Command::Status => status(davinci, subscriptions, timezone, chat).await,
+    Command::Help => HELP.to_string(),
+  };
+
+  telegram.send_text(chat, &reply).await
+}
+
+/// The explicitly requested class or all classes the chat is subscribed to.
+async fn classes<'a>(
+  davinci: &'a Davinci,
+  subscriptions: &Subscriptions,
+  chat: i64,
+  class: Option<&str>,
+) -> Vec<&'a Class> {
+  let registry = davinci.timetables().classes();
+
+  match class {
+    Some(class) => registry.resolve(class).into_iter().collect(),
+    None => subscriptions
+      .classes(chat)
+      .await
+      .iter()
+      .filter_map(|class| registry.resolve(class))
+      .collect(),
+  }
+}
+
+fn no_plan(class: &Class, err: anyhow::Error) -> String {
+  format!(
+    "Kein Plan für {}: {}",
+    escape(&class.name),
+    escape(&err.to_string())
+  )
+}
+
+fn available_classes(davinci: &Davinci) -> String {
+  davinci
+    .timetables()
+    .classes()
+    .iter()
+    .map(|class| class.name.as_str())
+    .collect::<Vec<&str>>()
+    .join(", ")
+}
+
+async fn status(
+  davinci: &Davinci,
+  subscriptions: &Subscriptions,
+  timezone: &Tz,
+  chat: i64,
+) -> String {
+  let now = OffsetDateTime::now_utc();
+  let mut text = match davinci.data().await.as_ref() {
+    None => "Der Vertretungsplan wurde noch nicht abgerufen.".to_string(),
+    Some(data) => {
+      let mut text = format!(
+        "Zuletzt vor {} abgerufen, {} Einträge.",
+        format_duration((now - data.last_checked).unsigned_abs()),
+        data.rows.len()
+      );
+      if let Some(last_modified) = data.last_modified {
+        write!(
+          text,
+          " Zuletzt vor {} geändert.",
+          format_duration((now - last_modified).unsigned_abs())
+        )
+        .unwrap();
+      }
+      let stats = davinci.stats().await;
+      if stats.crawls > 0 {
+        write!(
+          text,
+          "\n{} Abrufe, davon {} unvollständig und {} fehlgeschlagen. Beim letzten Abruf {} von {} Seiten unverändert.",
+          stats.crawls, stats.partial, stats.failed, stats.last.not_modified, stats.last.pages
+        )
+        .unwrap();
+      }
+      text
+    }
+  };
+
+  let classes = subscriptions.classes(chat).await;
+  if classes.is_empty() {
+    text.push_str("\nKeine Klasse abonniert.");
+  } else {
+    write!(text, "\nAbonniert: {}", escape(&classes.join(", "))).unwrap();
+  }
+
+  let courses = subscriptions.courses(chat).await;
+  if !courses.is_empty() {
+    write!(text, "\nKurse: {}", escape(&courses.to_string())).unwrap();
+  }
+
+  if matches!(
+    schedule::now(timezone).weekday(),
+    Weekday::Saturday | Weekday::Sunday
+  ) {
+    text.push_str("\nEs ist Wochenende.");
+  }
+
+  text
+}
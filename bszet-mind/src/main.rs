@@ -1,8 +1,10 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
 use std::iter::once;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -14,29 +16,52 @@ use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::{body, Extension, Router, Server};
-use clap::{arg, Parser};
+use clap::{Parser, ValueEnum};
 use include_dir::{include_dir, Dir};
 use reqwest::Url;
-use time::{Date, OffsetDateTime, Weekday};
+use time::{Date, OffsetDateTime, PrimitiveDateTime};
+use time_tz::{OffsetDateTimeExt, Tz};
 use tokio::select;
 use tokio::time::Instant;
 use tower_http::sensitive_headers::SetSensitiveRequestHeadersLayer;
 use tower_http::trace::TraceLayer;
 use tower_http::validate_request::ValidateRequestHeaderLayer;
-use tracing::{error, info, Level};
+use tracing::{error, info, warn, Level};
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
-use bszet_davinci::Davinci;
-use bszet_image::WebToImageConverter;
-use bszet_notify::telegram::Telegram;
-
-use crate::api::davinci::{html_plan, timetable};
-use crate::ascii::table;
+use bszet_davinci::class::Class;
+use bszet_davinci::courses::Courses;
+use bszet_davinci::crawl::CrawlOutcome;
+use bszet_davinci::diff::Diff;
+use bszet_davinci::iteration::IterationCalendar;
+use bszet_davinci::kinds::ChangeKinds;
+use bszet_davinci::lessons::LessonMode;
+use bszet_davinci::storage::FileStorage;
+use bszet_davinci::subjects::SubjectDictionary;
+use bszet_davinci::timetable::{Subject, Timetables};
+use bszet_davinci::{AppliedDay, Davinci, Row};
+use bszet_image::{ImageRenderer, NativeRenderer, Plan, WebRenderer, WeekCell, WeekDay, WeekPlan};
+use bszet_notify::telegram::{Telegram, TelegramChat};
+use bszet_notify::{Notification, Notifier};
+
+use crate::api::calendar::{calendar, Feed};
+use crate::api::davinci::{changes, html_plan, html_week, stats, timetable, week};
+use crate::api::iteration::iterations;
+use crate::bells::Bells;
+use crate::recipients::{Recipient, Recipients};
+use crate::schedule::{notification_date, parse_timezone, Digest, Overview, Schedule, Target};
+use crate::subscriptions::Subscriptions;
 
 mod api;
 mod ascii;
+mod bells;
+mod bot;
+mod message;
+mod recipients;
+mod schedule;
+mod subscriptions;
 
 #[cfg(test)]
 mod tests;
@@ -60,7 +85,7 @@ struct Args {
   #[arg(long, short, env = "BSZET_MIND_TELEGRAM_TOKEN")]
   telegram_token: String,
   #[arg(long, short, env = "BSZET_MIND_CHAT_IDS", value_delimiter = ',')]
-  chat_ids: Vec<i64>,
+  chat_ids: Vec<Chat>,
   #[arg(
     long,
     short,
@@ -94,6 +119,92 @@ struct Args {
   api_token: String,
   #[arg(long, env = "BSZET_MIND_ENVIRONMENT")]
   environment: Option<String>,
+  #[arg(long, env = "BSZET_MIND_TIMETABLES", default_value = "timetables")]
+  timetables: PathBuf,
+  #[arg(long, env = "BSZET_MIND_CLASS", default_value = "IGD21")]
+  class: String,
+  #[arg(long, env = "BSZET_MIND_CALENDAR", default_value = "calendar.toml")]
+  calendar: PathBuf,
+  #[arg(long, env = "BSZET_MIND_DATA_DIR")]
+  data_dir: Option<PathBuf>,
+  #[arg(long, env = "BSZET_MIND_RECIPIENTS")]
+  recipients: Option<PathBuf>,
+  #[arg(
+    long,
+    value_enum,
+    env = "BSZET_MIND_IMAGE_RENDERER",
+    default_value = "webdriver"
+  )]
+  image_renderer: Renderer,
+  #[arg(
+    long,
+    env = "BSZET_MIND_TIMEZONE",
+    default_value = "Europe/Berlin",
+    value_parser = parse_timezone
+  )]
+  timezone: &'static Tz,
+  #[arg(long, env = "BSZET_MIND_SCHEDULE")]
+  schedule: Option<PathBuf>,
+  /// `blocks` if the plan lists periods and the timetables double-period blocks, otherwise `periods`
+  #[arg(long, env = "BSZET_MIND_LESSON_MODE", default_value = "blocks")]
+  lesson_mode: LessonMode,
+  /// Mapping of the types of change, without only the basic types are applied
+  #[arg(long, env = "BSZET_MIND_CHANGE_KINDS")]
+  change_kinds: Option<PathBuf>,
+  /// Dictionary of the subject codes, without the shipped subjects are used
+  #[arg(long, env = "BSZET_MIND_SUBJECTS")]
+  subjects: Option<PathBuf>,
+  /// Start and end of the lessons, without the blocks of the BSZ ET are used
+  #[arg(long, env = "BSZET_MIND_BELLS")]
+  bells: Option<PathBuf>,
+  /// iCalendar feeds as `<class>:<secret>`, without no feed is served
+  #[arg(long, env = "BSZET_MIND_CALENDAR_FEEDS", value_delimiter = ',')]
+  calendar_feeds: Vec<Feed>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Renderer {
+  Webdriver,
+  Native,
+}
+
+/// Chat to notify, either just the chat id for the default class
+/// or `<chat id>:<class>[+<class>...]` to subscribe to one or more classes.
+#[derive(Clone, Debug, PartialEq)]
+struct Chat {
+  id: i64,
+  classes: Vec<String>,
+}
+
+impl Chat {
+  fn classes<'a>(&'a self, args: &'a Args) -> Vec<&'a str> {
+    if self.classes.is_empty() {
+      vec![args.class.as_str()]
+    } else {
+      self.classes.iter().map(String::as_str).collect()
+    }
+  }
+}
+
+impl FromStr for Chat {
+  type Err = anyhow::Error;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    Ok(match value.split_once(':') {
+      None => Chat {
+        id: i64::from_str(value.trim())?,
+        classes: Vec::new(),
+      },
+      Some((id, classes)) => Chat {
+        id: i64::from_str(id.trim())?,
+        classes: classes
+          .split('+')
+          .map(|class| class.trim().to_string())
+          .filter(|class| !class.is_empty())
+          .collect(),
+      },
+    })
+  }
 }
 
 #[tokio::main]
@@ -133,38 +244,185 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn real_main(args: Args) -> anyhow::Result<()> {
-  let davinci = Arc::new(Davinci::new(
-    args.entrypoint.clone(),
-    args.username.clone(),
-    args.password.clone(),
-  ));
+  let subjects = match &args.subjects {
+    Some(path) => SubjectDictionary::load(path)?,
+    None => SubjectDictionary::default(),
+  };
 
-  let args2 = args.clone();
-  let davinci2 = davinci.clone();
+  let timetables = Timetables::load(&args.timetables, subjects)?;
+  for class in
+    once(args.class.as_str()).chain(args.chat_ids.iter().flat_map(|chat| chat.classes(&args)))
+  {
+    if timetables.get(class).is_none() {
+      return Err(anyhow!(
+        "No timetable for class {} in {}",
+        class,
+        args.timetables.display()
+      ));
+    }
+  }
+
+  let change_kinds = match &args.change_kinds {
+    Some(path) => ChangeKinds::load(path)?,
+    None => ChangeKinds::default(),
+  };
+
+  // an outdated calendar knows no iteration for any day, which is expected during the summer
+  // holidays, so keep serving and report it as error to get it updated
+  let school_year = IterationCalendar::load(&args.calendar)?;
+  let today = schedule::now(args.timezone).date();
+  if school_year.has_ended(today) {
+    error!(
+      "Calendar {} ended before {}, it needs the current school year",
+      args.calendar.display(),
+      today
+    );
+  }
+
+  let davinci = Arc::new(
+    Davinci::new(
+      args.entrypoint.clone(),
+      args.username.clone(),
+      args.password.clone(),
+      timetables,
+      school_year,
+      args
+        .data_dir
+        .as_ref()
+        .map(|dir| FileStorage::new(dir.join("davinci.json"))),
+    )
+    .with_lesson_mode(args.lesson_mode)
+    .with_change_kinds(change_kinds),
+  );
+
+  if !davinci.restore().await? {
+    info!("No previous substitution plan to restore");
+  }
+
+  // configured chats are only used until the first subscription is stored
+  let seed = args
+    .chat_ids
+    .iter()
+    .flat_map(|chat| {
+      chat
+        .classes(&args)
+        .into_iter()
+        .filter_map(|class| davinci.timetables().classes().resolve(class))
+        .map(|class| (chat.id, class.name.clone()))
+    })
+    .collect::<Vec<(i64, String)>>();
+  let subscriptions = Arc::new(
+    Subscriptions::load(
+      args
+        .data_dir
+        .as_ref()
+        .map(|dir| dir.join("subscriptions.json")),
+      seed,
+      davinci.timetables().subjects(),
+    )
+    .await?,
+  );
+
+  let recipients = match &args.recipients {
+    Some(path) => Recipients::load(path, davinci.timetables().classes())?,
+    None => Recipients::default(),
+  };
+
+  let renderer: Box<dyn ImageRenderer> = match args.image_renderer {
+    Renderer::Webdriver => Box::new(WebRenderer::new(
+      args.gecko_driver_url.clone(),
+      args.internal_url.clone(),
+    )),
+    Renderer::Native => Box::new(NativeRenderer::new(
+      font("Inter-Regular.woff2")?,
+      font("Inter-Bold.woff2")?,
+    )?),
+  };
+
+  let schedule = match &args.schedule {
+    Some(path) => Schedule::load(path)?,
+    None => Schedule::default(),
+  };
+
+  let bells = Arc::new(match &args.bells {
+    Some(path) => Bells::load(path)?,
+    None => Bells::default(),
+  });
 
   let router = Router::new()
     .route("/davinci/:date/:class", get(timetable))
-    .layer(Extension(davinci2.clone()))
+    .route("/davinci/week/:week/:class", get(week))
+    .route("/davinci/changes", get(changes))
+    .route("/davinci/stats", get(stats))
+    .route("/iterations", get(iterations))
+    .layer(Extension(davinci.clone()))
+    .layer(Extension(args.timezone))
     .layer(ValidateRequestHeaderLayer::bearer(&args.api_token))
     .layer(SetSensitiveRequestHeadersLayer::new(once(AUTHORIZATION)))
     .layer(TraceLayer::new_for_http());
 
+  let mut feeds = args.calendar_feeds.clone();
+  for feed in &mut feeds {
+    feed.class = davinci
+      .timetables()
+      .get(&feed.class)
+      .map(|(class, _)| class.name.clone())
+      .ok_or_else(|| anyhow!("Unknown class {} of calendar feed", feed.class))?;
+  }
+
+  // calendar apps can not send the API token, the feeds are authorized by the secret in their
+  // path instead, which is why they are not traced either
+  let feed_router = Router::new()
+    .route("/calendar/:secret/:file", get(calendar))
+    .layer(Extension(davinci.clone()))
+    .layer(Extension(args.timezone))
+    .layer(Extension(bells.clone()))
+    .layer(Extension(Arc::new(feeds)));
+
   let internal_router = Router::new()
     .route("/davinci/:date", get(html_plan))
+    .route("/davinci/week/:week/:class", get(html_week))
     .route("/static/*path", get(static_path))
-    .layer(Extension(davinci2.clone()))
+    .layer(Extension(davinci.clone()))
     .layer(TraceLayer::new_for_http());
 
+  let telegram = Arc::new(Telegram::new(&args.telegram_token)?);
+  let context = Context {
+    args: args.clone(),
+    davinci: davinci.clone(),
+    subscriptions: subscriptions.clone(),
+    recipients,
+    renderer,
+    schedule,
+    bells: bells.clone(),
+    telegram: telegram.clone(),
+  };
+  let mut state = State {
+    last_run: None,
+    notified: davinci
+      .data()
+      .await
+      .as_ref()
+      .map(|data| data.rows.clone())
+      .unwrap_or_default(),
+    behind: HashMap::new(),
+    undelivered: BTreeMap::new(),
+  };
+
   tokio::spawn(async move {
-    let args2 = args2;
-    let davinci2 = davinci2;
     loop {
-      if let Err(err) = iteration(&args2, &davinci2).await {
+      if let Err(err) = iteration(&context, &mut state).await {
         error!("Error while executing loop: {}", err);
       }
     }
   });
 
+  let davinci3 = davinci.clone();
+  let args3 = args.clone();
+  tokio::spawn(async move {
+    bot::run(&telegram, &davinci3, &subscriptions, &bells, args3.timezone).await;
+  });
+
   info!("Listening on http://{}...", args.listen_addr);
   info!(
     "Listening on http://{}... (internal)",
@@ -172,7 +430,7 @@ async fn real_main(args: Args) -> anyhow::Result<()> {
   );
 
   select! {
-    public = Server::bind(&args.listen_addr).serve(router.into_make_service()) => {
+    public = Server::bind(&args.listen_addr).serve(router.merge(feed_router).into_make_service()) => {
       public?;
     }
     internal = Server::bind(&args.internal_listen_addr).serve(internal_router.into_make_service()) => {
@@ -185,7 +443,7 @@ async fn real_main(args: Args) -> anyhow::Result<()> {
 
 async fn static_path(Path(path): Path<String>) -> impl IntoResponse {
   let path = path.trim_start_matches('/');
-  let mime_type = match path.split('.').last() {
+  let mime_type = match path.split('.').next_back() {
     Some("css") => "text/css",
     Some("woff2") => "font/woff2",
     _ => "application/octet-stream",
@@ -207,166 +465,435 @@ async fn static_path(Path(path): Path<String>) -> impl IntoResponse {
   }
 }
 
-async fn iteration(args: &Args, davinci: &Davinci) -> anyhow::Result<()> {
-  let result = match davinci.update().await {
-    Err(err) => Err(anyhow!(format!(
-      "Error executing davinci update schedule: {}",
-      err
-    ))),
-    Ok(false) => {
-      let now = OffsetDateTime::now_utc();
-
-      if now.hour() == 15 && now.minute() <= 14 {
-        info!("Send 15 o'clock notification");
-        send_notifications(args, davinci).await
-      } else {
-        info!("Nothing changed");
-        Ok(())
-      }
-    }
-    Ok(true) => {
-      info!("Detected changes, sending notifications...");
+/// Everything needed to crawl and notify.
+struct Context {
+  args: Args,
+  davinci: Arc<Davinci>,
+  subscriptions: Arc<Subscriptions>,
+  recipients: Recipients,
+  renderer: Box<dyn ImageRenderer>,
+  schedule: Schedule,
+  bells: Arc<Bells>,
+  telegram: Arc<Telegram>,
+}
 
-      send_notifications(args, davinci).await
-    }
-  };
+struct State {
+  last_run: Option<OffsetDateTime>,
+  /// Rows at the time of the last change notification
+  notified: HashSet<Row>,
+  /// Rows at the last change notification of the targets a later one failed for
+  behind: HashMap<String, HashSet<Row>>,
+  /// Targets of digests which could not be delivered yet
+  undelivered: BTreeMap<Overview, BTreeSet<String>>,
+}
+
+async fn iteration(context: &Context, state: &mut State) -> anyhow::Result<()> {
+  let Context {
+    args,
+    davinci,
+    schedule,
+    bells,
+    ..
+  } = context;
 
-  if let Err(err) = result {
-    error!("Unable to execute iteration: {:?}", err);
+  let now = OffsetDateTime::now_utc();
+  let last = state
+    .last_run
+    .replace(now)
+    .unwrap_or(now - time::Duration::minutes(schedule.interval as i64));
+
+  match davinci.update().await {
+    CrawlOutcome::Complete(_) => {}
+    CrawlOutcome::Partial(incomplete) => {
+      warn!("Keeping previous substitution plan: {}", incomplete)
+    }
+    CrawlOutcome::Failed(err) => error!("Error executing davinci update schedule: {}", err),
   }
 
-  await_next_execution().await;
+  let day_off = schedule.is_day_off(now, args.timezone, davinci.calendar());
+
+  // changes since the last notification, including those batched during quiet hours
+  let rows = davinci
+    .data()
+    .await
+    .as_ref()
+    .map(|data| data.rows.clone())
+    .unwrap_or_default();
+  let diff = Diff::between(&state.notified, &rows);
+
+  if diff.is_empty() && state.behind.is_empty() {
+    info!("Nothing changed");
+  } else if day_off || schedule.is_quiet(now, args.timezone) {
+    info!("Detected changes, postponing notifications");
+  } else {
+    info!("Detected changes, sending notifications...");
+
+    // targets a notification failed for get all changes since the last one they received
+    let mut baselines: Vec<(HashSet<Row>, HashSet<String>)> = Vec::new();
+    for (target, notified) in state.behind.drain() {
+      match baselines.iter_mut().find(|(other, _)| *other == notified) {
+        Some((_, targets)) => {
+          targets.insert(target);
+        }
+        None => baselines.push((notified, HashSet::from([target]))),
+      }
+    }
+    let behind = |target: &Target| {
+      let target = target.to_string();
+      baselines
+        .iter()
+        .any(|(_, targets)| targets.contains(&target))
+    };
+
+    let overview = Overview::Day(notification_date(now, args.timezone, bells));
+    if !diff.is_empty() {
+      let failed =
+        send_notifications(context, overview, Some(&diff), &|target| !behind(&target)).await;
+      for target in failed {
+        state.behind.insert(target, state.notified.clone());
+      }
+    }
+    for (notified, targets) in &baselines {
+      let diff = Diff::between(notified, &rows);
+      let failed = send_notifications(context, overview, Some(&diff), &|target| {
+        targets.contains(&target.to_string())
+      })
+      .await;
+      for target in failed {
+        state.behind.insert(target, notified.clone());
+      }
+    }
 
-  Ok(())
-}
+    state.notified = rows;
+  }
 
-async fn send_notifications(args: &Args, davinci: &Davinci) -> anyhow::Result<()> {
-  let mut now = OffsetDateTime::now_utc();
+  // digests bound to a weekday are also sent on days off, like the week on sunday
+  let due = |digest: &Digest| {
+    (!day_off || digest.weekday.is_some()) && digest.is_due(last, now, args.timezone)
+  };
 
-  if now.hour() >= 15 {
-    now += time::Duration::days(1);
+  // digests which could not be delivered are retried until their day or week is over
+  let today = now.to_timezone(args.timezone).date();
+  state
+    .undelivered
+    .retain(|overview, _| !overview.is_over(today));
+  let mut overviews = schedule
+    .all_digests()
+    .filter(|digest| due(digest))
+    .map(|digest| digest.overview(now, args.timezone))
+    .collect::<BTreeSet<Overview>>();
+  if !schedule.is_quiet(now, args.timezone) {
+    overviews.extend(state.undelivered.keys());
   }
 
-  match now.weekday() {
-    Weekday::Saturday => now += time::Duration::days(2),
-    Weekday::Sunday => now += time::Duration::days(1),
-    _ => {}
+  // one notification per day or week shown, each target only gets its own digests
+  for overview in overviews {
+    info!("Send digest for {:?}", overview);
+
+    let undelivered = state.undelivered.remove(&overview).unwrap_or_default();
+    let targets = |target: Target| {
+      undelivered.contains(&target.to_string())
+        || schedule
+          .digests(target)
+          .iter()
+          .any(|digest| due(digest) && digest.overview(now, args.timezone) == overview)
+    };
+    let failed = send_notifications(context, overview, None, &targets).await;
+    if !failed.is_empty() {
+      state.undelivered.insert(overview, failed);
+    }
   }
 
-  let (last_modified, day, unknown_changes, iteration) =
-    davinci.get_applied_timetable(now.date()).await?;
+  let duration = schedule.until_next_execution(OffsetDateTime::now_utc(), args.timezone);
+  info!(
+    "Next execution in {:0>2}:{:0>2} minutes",
+    duration.as_secs() / 60,
+    duration.as_secs() % 60,
+  );
+  tokio::time::sleep_until(Instant::now() + duration).await;
 
-  let table = table(day);
+  Ok(())
+}
 
-  let telegram = Telegram::new(&args.telegram_token)?;
-  let image_result = match render_images(&args.gecko_driver_url, &args.internal_url, davinci).await
-  {
-    Ok(result) => result,
-    Err(err) => {
-      error!("Error while rendering images: {}", err);
-      None
+/// Notifies the `targets` subscribed to the classes affected by `diff` or,
+/// without diff, to any class about the plan of the `overview`. Returns the
+/// targets which could not be notified.
+async fn send_notifications(
+  context: &Context,
+  overview: Overview,
+  diff: Option<&Diff>,
+  targets: &(dyn Fn(Target) -> bool + Sync),
+) -> BTreeSet<String> {
+  let Context {
+    davinci,
+    subscriptions,
+    recipients,
+    renderer,
+    telegram,
+    ..
+  } = context;
+
+  let today = schedule::now(context.args.timezone).date();
+  let mut failed = BTreeSet::new();
+
+  for class in davinci.timetables().classes().iter() {
+    let mut chats = Vec::new();
+    for chat in subscriptions.chats(&class.name).await {
+      if targets(Target::Chat(chat)) {
+        chats.push((
+          Target::Chat(chat).to_string(),
+          subscriptions.courses(chat).await,
+          TelegramChat::new(telegram.clone(), chat),
+        ));
+      }
     }
-  };
+    let configured = recipients
+      .of(class)
+      .filter(|recipient| targets(Target::Recipient(&recipient.name)))
+      .collect::<Vec<&Recipient>>();
 
-  for id in &args.chat_ids {
-    let age = last_modified
-      .map(|last_modified| (OffsetDateTime::now_utc() - last_modified).unsigned_abs())
-      .unwrap_or_else(|| Duration::from_secs(0));
-
-    let mut text = format!(
-      "Vertretungsplan für {} den {}. {} {}, Turnus {}. Zuletzt vor {} aktualisiert.\n```\n{}```",
-      now.weekday(),
-      now.day(),
-      now.month(),
-      now.year(),
-      iteration,
-      format_duration(age),
-      table,
-    );
+    if chats.is_empty() && configured.is_empty() {
+      continue;
+    }
 
-    if !unknown_changes.is_empty() {
-      writeln!(text, "\n\nÄnderungen, die nicht angewendet werden konnten:").unwrap();
-      for row in &unknown_changes {
-        writeln!(text, "- {row:?}").unwrap();
+    // only notify about changes of upcoming days affecting the class
+    let diff =
+      diff.map(|diff| diff.filter(|row| row.date >= today && class.affected_by(&row.class)));
+    if let Some(diff) = &diff {
+      if diff.is_empty() {
+        info!("No relevant changes for class {}", class);
+        continue;
       }
     }
 
-    match &image_result {
-      Some(images) => {
-        telegram.send_images(*id, text.as_str(), images).await?;
+    // the week image depends on the courses and is rendered per audience
+    let images = match overview {
+      Overview::Day(_) => match render_images(renderer.as_ref(), davinci, class).await {
+        Ok(result) => result,
+        Err(err) => {
+          error!("Error while rendering images: {}", err);
+          None
+        }
+      },
+      Overview::Week(_) => None,
+    };
+
+    // chats with the same courses share a notification, configured recipients get all lessons
+    let mut audiences: Vec<(Courses, Notifiers)> = Vec::new();
+    let notifiers = chats
+      .iter()
+      .map(|(name, courses, chat)| (courses.clone(), name.as_str(), chat as &dyn Notifier))
+      .chain(configured.iter().map(|recipient| {
+        (
+          Courses::default(),
+          recipient.name.as_str(),
+          recipient.notifier.as_ref(),
+        )
+      }));
+    for (courses, name, notifier) in notifiers {
+      match audiences.iter_mut().find(|(other, _)| *other == courses) {
+        Some((_, notifiers)) => notifiers.push((name, notifier)),
+        None => audiences.push((courses, vec![(name, notifier)])),
       }
-      None => {
-        telegram.send_text(*id, text.as_str()).await?;
+    }
+
+    let electives = davinci.timetables().electives(&class.name);
+    for (courses, notifiers) in audiences {
+      let diff = diff
+        .as_ref()
+        .map(|diff| diff.filter(|row| courses.affects(row, &electives)));
+      if let Some(diff) = &diff {
+        if diff.is_empty() {
+          info!(
+            "No relevant changes for class {} with courses {}",
+            class, courses
+          );
+          continue;
+        }
+      }
+
+      let notification =
+        match notification(context, class, overview, diff.as_ref(), &courses, &images).await {
+          Ok(notification) => notification,
+          Err(err) => {
+            error!("Unable to notify about class {}: {:?}", class, err);
+            failed.extend(notifiers.iter().map(|(name, _)| name.to_string()));
+            continue;
+          }
+        };
+
+      // a failing recipient must not prevent notifying the others
+      for (name, notifier) in notifiers {
+        if let Err(err) = notifier.notify(&notification).await {
+          error!("Unable to notify {} about class {}: {:?}", name, class, err);
+          failed.insert(name.to_string());
+        }
       }
     }
   }
 
-  Ok(())
+  failed
 }
 
+/// Notification of an audience with the same `courses` about the `overview`.
+async fn notification(
+  context: &Context,
+  class: &Class,
+  overview: Overview,
+  diff: Option<&Diff>,
+  courses: &Courses,
+  images: &Option<Vec<Vec<u8>>>,
+) -> anyhow::Result<Notification> {
+  let Context {
+    args,
+    davinci,
+    renderer,
+    bells,
+    ..
+  } = context;
+
+  let now = schedule::now(args.timezone);
+  let (text, images) = match overview {
+    Overview::Day(date) => {
+      let mut text = message::plan(davinci, class, date, diff, courses).await?;
+      if date == now.date() {
+        let (_, day, _, _) = davinci
+          .get_applied_timetable(&class.name, date, courses)
+          .await?;
+        let now = PrimitiveDateTime::new(now.date(), now.time());
+        if let Some(next) = message::next_lesson(bells, &day, now) {
+          write!(text, "\n\n{next}").unwrap();
+        }
+      }
+      (text, images.clone().unwrap_or_default())
+    }
+    Overview::Week(monday) => {
+      let image = match render_week(renderer.as_ref(), davinci, class, monday, courses).await {
+        Ok(image) => vec![image],
+        Err(err) => {
+          error!("Error while rendering week image: {}", err);
+          Vec::new()
+        }
+      };
+      (message::week(davinci, class, monday, courses).await, image)
+    }
+  };
+
+  Ok(Notification {
+    title: format!("Vertretungsplan {class}"),
+    text,
+    images,
+    changes: diff.map(message::entries).unwrap_or_default(),
+  })
+}
+
+type Notifiers<'a> = Vec<(&'a str, &'a dyn Notifier)>;
+
 async fn render_images(
-  gecko_driver_url: &Url,
-  base_url: &Url,
+  renderer: &dyn ImageRenderer,
   davinci: &Davinci,
+  class: &Class,
 ) -> anyhow::Result<Option<Vec<Vec<u8>>>> {
-  let web_img_conv = WebToImageConverter::new(gecko_driver_url.as_str()).await?;
+  // collect the plans first to not block updates while rendering
+  let plans = match davinci.data().await.as_ref() {
+    Some(data) => data
+      .dates()
+      .into_iter()
+      .map(|date| Plan {
+        date,
+        rows: data
+          .table(date)
+          .into_iter()
+          .map(<[String]>::to_vec)
+          .collect(),
+        classes: class.spellings().map(str::to_string).collect(),
+      })
+      .collect::<Vec<Plan>>(),
+    None => return Ok(None),
+  };
 
-  match davinci.data().await.as_ref() {
-    Some(data) => {
-      let mut images = Vec::new();
+  let mut images = Vec::new();
+  for plan in &plans {
+    images.push(renderer.render(plan).await?);
+  }
 
-      let dates = data
-        .rows
-        .iter()
-        .map(|row| row.date)
-        .collect::<HashSet<Date>>();
-      let mut dates = dates.into_iter().collect::<Vec<Date>>();
-      dates.sort();
-
-      for date in dates {
-        images.push(
-          web_img_conv
-            .create_image(
-              base_url
-                .join(&format!(
-                  "davinci/{}-{:0>2}-{:0>2}?class=IGD21,IGD 21",
-                  date.year(),
-                  date.month() as u8,
-                  date.day()
-                ))?
-                .as_str(),
-            )
-            .await?,
-        )
-      }
+  Ok(Some(images))
+}
 
-      Ok(Some(images))
-    }
+async fn render_week(
+  renderer: &dyn ImageRenderer,
+  davinci: &Davinci,
+  class: &Class,
+  monday: Date,
+  courses: &Courses,
+) -> anyhow::Result<Vec<u8>> {
+  let (_, days, _) = davinci
+    .get_applied_week(&class.name, monday, courses)
+    .await?;
+
+  renderer
+    .render_week(&week_plan(class, monday, courses, &days))
+    .await
+}
 
-    None => Ok(None),
+/// Grid of the week, the cells of a day hold all lessons with the same number.
+pub(crate) fn week_plan(
+  class: &Class,
+  monday: Date,
+  courses: &Courses,
+  days: &[AppliedDay],
+) -> WeekPlan {
+  let lessons = days
+    .iter()
+    .flat_map(|day| &day.lessons)
+    .map(|lesson| lesson.lesson)
+    .max()
+    .unwrap_or(0);
+
+  WeekPlan {
+    class: class.name.clone(),
+    monday,
+    courses: courses.to_string(),
+    days: days
+      .iter()
+      .map(|day| WeekDay {
+        title: format!(
+          "{} {:0>2}.{:0>2}.",
+          day.date.weekday(),
+          day.date.day(),
+          day.date.month() as u8
+        ),
+        cells: (1..=lessons)
+          .map(|number| {
+            let mut cell = WeekCell::default();
+            for lesson in day.lessons.iter().filter(|lesson| lesson.lesson == number) {
+              cell.lines.push(match &lesson.place {
+                Some(place) => format!("{} {}", lesson.subject, place),
+                None => lesson.subject.to_string(),
+              });
+              if let Some(notice) = &lesson.notice {
+                cell.lines.push(notice.clone());
+              }
+              cell.changed |=
+                matches!(lesson.subject, Subject::Cancel(_)) || lesson.notice.is_some();
+            }
+            cell
+          })
+          .collect(),
+      })
+      .collect(),
   }
 }
 
-async fn await_next_execution() {
-  let now = OffsetDateTime::now_utc();
-
-  let now_min = now.minute() as u64;
-  let now_min_to_last_15 = now_min % 15;
-  let now_min_to_next_15 = 15 - now_min_to_last_15;
-  let now_sec_to_next_15 = now_min_to_next_15 * 60;
-  let now_sec_to_next_15_prec = now_sec_to_next_15 - now.second() as u64;
-  let duration = Duration::from_secs(now_sec_to_next_15_prec);
-
-  let sleep_until = Instant::now() + duration;
-  info!(
-    "Next execution in {:0>2}:{:0>2} minutes",
-    now_sec_to_next_15_prec / 60,
-    now_sec_to_next_15_prec % 60,
-  );
-  tokio::time::sleep_until(sleep_until).await;
+fn font(name: &str) -> anyhow::Result<&'static [u8]> {
+  STATIC_DIR
+    .get_file(name)
+    .map(|file| file.contents())
+    .ok_or_else(|| anyhow!("Missing font {}", name))
 }
 
-fn format_duration(duration: Duration) -> String {
+/// Formats `duration` in German using its two largest non-zero units, e.g. "2 Tagen und einer Stunde".
+pub(crate) fn format_duration(duration: Duration) -> String {
   let secs = duration.as_secs();
 
   let units = [
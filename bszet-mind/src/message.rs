@@ -0,0 +1,217 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+use bszet_davinci::class::Class;
+use bszet_davinci::courses::Courses;
+use bszet_davinci::diff::Diff;
+use bszet_davinci::timetable::Lesson;
+use bszet_davinci::{Davinci, Row};
+use bszet_notify::{ChangeEntry, ChangeKind};
+
+use crate::ascii::table;
+use crate::bells::Bells;
+use crate::format_duration;
+
+/// Applied timetable of `class` for `date`, including the rows that could not be applied,
+/// limited to the lessons of `courses`.
+pub async fn plan(
+  davinci: &Davinci,
+  class: &Class,
+  date: Date,
+  diff: Option<&Diff>,
+  courses: &Courses,
+) -> anyhow::Result<String> {
+  let (last_modified, day, unknown_changes, iteration) = davinci
+    .get_applied_timetable(&class.name, date, courses)
+    .await?;
+
+  let table = table(day);
+
+  let age = last_modified
+    .map(|last_modified| (OffsetDateTime::now_utc() - last_modified).unsigned_abs())
+    .unwrap_or_else(|| Duration::from_secs(0));
+
+  let mut text = format!(
+    "Vertretungsplan {} für {} den {}. {} {}, Turnus {}. Zuletzt vor {} aktualisiert.\n```\n{}```",
+    escape(&class.to_string()),
+    date.weekday(),
+    date.day(),
+    date.month(),
+    date.year(),
+    iteration,
+    format_duration(age),
+    table,
+  );
+
+  if let Some(diff) = diff {
+    write!(text, "\n\nÄnderungen:\n{}", changes(diff)).unwrap();
+  }
+
+  if !unknown_changes.is_empty() {
+    writeln!(text, "\n\nÄnderungen, die nicht angewendet werden konnten:").unwrap();
+    for row in &unknown_changes {
+      writeln!(text, "- {}", escape(&format!("{row:?}"))).unwrap();
+    }
+  }
+
+  Ok(text)
+}
+
+/// Applied timetables of `class` from monday to friday of the week starting at `monday`,
+/// including the rows that could not be applied.
+pub async fn week(davinci: &Davinci, class: &Class, monday: Date, courses: &Courses) -> String {
+  let mut text = format!(
+    "Woche von {} ab {}.{}.",
+    escape(&class.to_string()),
+    monday.day(),
+    monday.month() as u8
+  );
+
+  let (days, unapplied) = davinci
+    .get_applied_week(&class.name, monday, courses)
+    .await
+    .map(|(_, days, unapplied)| (days, unapplied))
+    .unwrap_or_default();
+
+  for day in days {
+    write!(
+      text,
+      "\n\n{} {}.{}.",
+      day.date.weekday(),
+      day.date.day(),
+      day.date.month() as u8
+    )
+    .unwrap();
+    match day.iteration {
+      Some(iteration) => write!(
+        text,
+        ", Turnus {}\n```\n{}```",
+        iteration,
+        table(day.lessons)
+      )
+      .unwrap(),
+      None => text.push_str(": kein Unterricht"),
+    }
+  }
+
+  if !unapplied.is_empty() {
+    write!(text, "\n\nÄnderungen, die nicht angewendet werden konnten:").unwrap();
+    for row in &unapplied {
+      write!(text, "\n- {}", escape(&format!("{row:?}"))).unwrap();
+    }
+  }
+
+  text
+}
+
+/// Lessons of `day` starting next after `now` like `Nächste Stunde in 20 Minuten: Ma in B11`.
+pub fn next_lesson(bells: &Bells, day: &[Lesson], now: PrimitiveDateTime) -> Option<String> {
+  let time = Time::from_hms(now.hour(), now.minute(), 0).unwrap();
+  let (start, lessons) = bells.next(now.weekday(), day, time)?;
+
+  let lessons = lessons
+    .iter()
+    .map(|lesson| match &lesson.place {
+      Some(place) => format!("{} in {}", lesson.subject, place),
+      None => lesson.subject.to_string(),
+    })
+    .collect::<Vec<String>>();
+
+  Some(format!(
+    "Nächste Stunde in {}: {}",
+    format_duration((start - time).unsigned_abs()),
+    escape(&lessons.join(", "))
+  ))
+}
+
+/// Lists the changes of the substitution plan, one line per row.
+pub fn changes(diff: &Diff) -> String {
+  let diff = diff.merge_moves();
+  let mut out = String::new();
+
+  for row in &diff.added {
+    writeln!(out, "- Neu: {}", escape(&describe(row))).unwrap();
+  }
+
+  for modification in &diff.modified {
+    writeln!(
+      out,
+      "- Geändert: {} (vorher {})",
+      escape(&describe(&modification.after)),
+      escape(&modification.before.change.to_string())
+    )
+    .unwrap();
+  }
+
+  for row in &diff.removed {
+    writeln!(out, "- Zurückgezogen: {}", escape(&describe(row))).unwrap();
+  }
+
+  out
+}
+
+/// Structured form of the changes for notifiers which process them further.
+pub fn entries(diff: &Diff) -> Vec<ChangeEntry> {
+  let entry = |kind, row: &Row, before: Option<&Row>| ChangeEntry {
+    kind,
+    date: format!(
+      "{}-{:0>2}-{:0>2}",
+      row.date.year(),
+      row.date.month() as u8,
+      row.date.day()
+    ),
+    classes: row.class.clone(),
+    lesson: row.change.lessons().from,
+    until: row.change.lessons().to,
+    description: row.change.to_string(),
+    before: before.map(|before| before.change.to_string()),
+  };
+
+  let diff = diff.merge_moves();
+  diff
+    .added
+    .iter()
+    .map(|row| entry(ChangeKind::Added, row, None))
+    .chain(diff.modified.iter().map(|modification| {
+      entry(
+        ChangeKind::Modified,
+        &modification.after,
+        Some(&modification.before),
+      )
+    }))
+    .chain(
+      diff
+        .removed
+        .iter()
+        .map(|row| entry(ChangeKind::Removed, row, None)),
+    )
+    .collect()
+}
+
+/// Escapes the characters which format Markdown, for text outside of code blocks.
+pub fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for char in text.chars() {
+    if matches!(char, '_' | '*' | '`' | '[') {
+      escaped.push('\\');
+    }
+    escaped.push(char);
+  }
+  escaped
+}
+
+fn describe(row: &Row) -> String {
+  // moves name both of their dates
+  if row.change.is_move() {
+    return row.change.to_string();
+  }
+
+  format!(
+    "{:0>2}.{:0>2}. {}",
+    row.date.day(),
+    row.date.month() as u8,
+    row.change
+  )
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use bszet_davinci::class::{Class, ClassRegistry};
+use bszet_davinci::format::load;
+use bszet_notify::config::NotifierConfig;
+use bszet_notify::Notifier;
+
+/// Additional recipients besides the telegram subscriptions, configured in a data file.
+#[derive(Default)]
+pub struct Recipients {
+  recipients: Vec<Recipient>,
+}
+
+pub struct Recipient {
+  pub name: String,
+  pub classes: Vec<String>,
+  pub notifier: Box<dyn Notifier>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecipientsFile {
+  #[serde(default)]
+  pub(crate) recipients: Vec<RecipientFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecipientFile {
+  pub(crate) name: String,
+  pub(crate) classes: Vec<String>,
+  pub(crate) notifier: NotifierConfig,
+}
+
+impl Recipients {
+  /// Loads the recipients at `path`, class names are resolved to their canonical name.
+  pub fn load(path: &Path, classes: &ClassRegistry) -> anyhow::Result<Self> {
+    load::<RecipientsFile>(path)?
+      .into_recipients(classes)
+      .map_err(|err| anyhow!("Invalid recipients {}: {}", path.display(), err))
+  }
+
+  /// Recipients subscribed to `class`.
+  pub fn of<'a>(&'a self, class: &'a Class) -> impl Iterator<Item = &'a Recipient> {
+    self
+      .recipients
+      .iter()
+      .filter(move |recipient| recipient.classes.contains(&class.name))
+  }
+}
+
+impl RecipientsFile {
+  pub(crate) fn into_recipients(self, classes: &ClassRegistry) -> anyhow::Result<Recipients> {
+    let recipients = self
+      .recipients
+      .into_iter()
+      .map(|recipient| {
+        let resolved = recipient
+          .classes
+          .iter()
+          .map(|class| {
+            classes
+              .resolve(class)
+              .map(|class| class.name.clone())
+              .ok_or_else(|| anyhow!("{}: no timetable for class {}", recipient.name, class))
+          })
+          .collect::<anyhow::Result<Vec<String>>>()?;
+
+        Ok(Recipient {
+          notifier: recipient
+            .notifier
+            .build()
+            .map_err(|err| anyhow!("{}: {}", recipient.name, err))?,
+          name: recipient.name,
+          classes: resolved,
+        })
+      })
+      .collect::<anyhow::Result<Vec<Recipient>>>()?;
+
+    Ok(Recipients { recipients })
+  }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use time::serde::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, Weekday};
+use time_tz::{timezones, OffsetDateTimeExt, PrimitiveDateTimeExt, Tz};
+
+use bszet_davinci::format::load;
+use bszet_davinci::iteration::IterationCalendar;
+
+use crate::bells::Bells;
+
+format_description!(pub(crate) hh_mm, Time, "[hour]:[minute]");
+
+/// Hour of the default digest.
+const AFTERNOON: u8 = 15;
+
+/// When to crawl and when to notify, times are in the timezone of the school.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Schedule {
+  /// Minutes between two crawls
+  #[serde(default = "default_interval")]
+  pub(crate) interval: u64,
+  /// Digests sent to all chats without own digests
+  #[serde(default = "default_digests")]
+  pub(crate) digests: Vec<Digest>,
+  /// Changes are batched and sent after the quiet hours
+  #[serde(default)]
+  pub(crate) quiet_hours: Option<QuietHours>,
+  /// Neither digests nor changes are sent on weekends
+  #[serde(default)]
+  pub(crate) skip_weekends: bool,
+  /// Neither digests nor changes are sent on holidays of the calendar
+  #[serde(default)]
+  pub(crate) skip_holidays: bool,
+  #[serde(default)]
+  pub(crate) chats: Vec<ChatSchedule>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Digest {
+  #[serde(with = "hh_mm")]
+  pub(crate) time: Time,
+  #[serde(default)]
+  pub(crate) day: DigestDay,
+  /// Only sent on this weekday, then also on days off
+  #[serde(default, deserialize_with = "weekday")]
+  pub(crate) weekday: Option<Weekday>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DigestDay {
+  /// The current or, on weekends, the next school day
+  Today,
+  /// The school day after the current day
+  #[default]
+  Next,
+  /// The timetables of the week of the next school day
+  Week,
+}
+
+/// What a notification shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Overview {
+  /// The plan of a day
+  Day(Date),
+  /// The timetables of the week starting at the monday
+  Week(Date),
+}
+
+impl Overview {
+  /// Whether the shown day or school week lies before `today`.
+  pub(crate) fn is_over(&self, today: Date) -> bool {
+    match *self {
+      Overview::Day(date) => date < today,
+      Overview::Week(monday) => monday + time::Duration::days(4) < today,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct QuietHours {
+  #[serde(with = "hh_mm")]
+  pub(crate) from: Time,
+  #[serde(with = "hh_mm")]
+  pub(crate) to: Time,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ChatSchedule {
+  pub(crate) id: i64,
+  pub(crate) digests: Vec<Digest>,
+}
+
+/// Receiver of a notification, either a telegram chat or a configured recipient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Target<'a> {
+  Chat(i64),
+  Recipient(&'a str),
+}
+
+impl Display for Target<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Target::Chat(id) => write!(f, "telegram chat {id}"),
+      Target::Recipient(name) => f.write_str(name),
+    }
+  }
+}
+
+fn default_interval() -> u64 {
+  15
+}
+
+fn default_digests() -> Vec<Digest> {
+  vec![Digest {
+    time: Time::from_hms(AFTERNOON, 0, 0).unwrap(),
+    day: DigestDay::Next,
+    weekday: None,
+  }]
+}
+
+impl Default for Schedule {
+  fn default() -> Self {
+    Self {
+      interval: default_interval(),
+      digests: default_digests(),
+      quiet_hours: None,
+      skip_weekends: false,
+      skip_holidays: false,
+      chats: Vec::new(),
+    }
+  }
+}
+
+impl Schedule {
+  pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+    let schedule = load::<Self>(path)?;
+    schedule
+      .validate()
+      .map_err(|err| anyhow!("Invalid schedule {}: {}", path.display(), err))?;
+    Ok(schedule)
+  }
+
+  pub(crate) fn validate(&self) -> anyhow::Result<()> {
+    if self.interval == 0 || self.interval > 24 * 60 {
+      return Err(anyhow!(
+        "interval is {} minutes, expected 1 to 1440",
+        self.interval
+      ));
+    }
+
+    if let Some(quiet_hours) = self.quiet_hours {
+      if quiet_hours.from == quiet_hours.to {
+        return Err(anyhow!("quiet hours must not be empty"));
+      }
+    }
+
+    let mut chats = BTreeSet::new();
+    for chat in &self.chats {
+      if !chats.insert(chat.id) {
+        return Err(anyhow!("chat {} is configured twice", chat.id));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Digests of `target`, chats can override the default digests.
+  pub(crate) fn digests(&self, target: Target) -> &[Digest] {
+    match target {
+      Target::Chat(id) => self
+        .chats
+        .iter()
+        .find(|chat| chat.id == id)
+        .map(|chat| chat.digests.as_slice())
+        .unwrap_or(&self.digests),
+      Target::Recipient(_) => &self.digests,
+    }
+  }
+
+  /// All configured digests, regardless of the target.
+  pub(crate) fn all_digests(&self) -> impl Iterator<Item = &Digest> {
+    self
+      .digests
+      .iter()
+      .chain(self.chats.iter().flat_map(|chat| chat.digests.iter()))
+  }
+
+  pub(crate) fn is_quiet(&self, now: OffsetDateTime, timezone: &Tz) -> bool {
+    let Some(QuietHours { from, to }) = self.quiet_hours else {
+      return false;
+    };
+    let time = now.to_timezone(timezone).time();
+
+    if from < to {
+      from <= time && time < to
+    } else {
+      // over midnight
+      from <= time || time < to
+    }
+  }
+
+  /// Whether notifications are suppressed for the whole day.
+  pub(crate) fn is_day_off(
+    &self,
+    now: OffsetDateTime,
+    timezone: &Tz,
+    calendar: &IterationCalendar,
+  ) -> bool {
+    let date = now.to_timezone(timezone).date();
+
+    (self.skip_weekends && matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday))
+      || (self.skip_holidays && calendar.is_holiday(date))
+  }
+
+  pub(crate) fn until_next_execution(&self, now: OffsetDateTime, timezone: &Tz) -> Duration {
+    until_next_execution(now, timezone, self.interval)
+  }
+}
+
+impl Digest {
+  /// Whether the digest time lies within `(last, now]`.
+  pub(crate) fn is_due(&self, last: OffsetDateTime, now: OffsetDateTime, timezone: &Tz) -> bool {
+    let first = last.to_timezone(timezone).date();
+    let mut date = now.to_timezone(timezone).date();
+
+    while date >= first {
+      let at = local(PrimitiveDateTime::new(date, self.time), timezone);
+      if last < at && at <= now && self.weekday.is_none_or(|day| day == date.weekday()) {
+        return true;
+      }
+      date = date.previous_day().unwrap();
+    }
+
+    false
+  }
+
+  pub(crate) fn date(&self, now: OffsetDateTime, timezone: &Tz) -> Date {
+    let today = now.to_timezone(timezone).date();
+
+    match self.day {
+      DigestDay::Today => next_school_day(today),
+      DigestDay::Next | DigestDay::Week => next_school_day(today + time::Duration::days(1)),
+    }
+  }
+
+  pub(crate) fn overview(&self, now: OffsetDateTime, timezone: &Tz) -> Overview {
+    let date = self.date(now, timezone);
+
+    match self.day {
+      DigestDay::Week => Overview::Week(monday(date)),
+      _ => Overview::Day(date),
+    }
+  }
+}
+
+/// Resolves a local time, times skipped by a DST switch are moved by an hour.
+pub(crate) fn local(date_time: PrimitiveDateTime, timezone: &Tz) -> OffsetDateTime {
+  date_time
+    .assume_timezone(timezone)
+    .take_first()
+    .or_else(|| {
+      (date_time + time::Duration::hours(1))
+        .assume_timezone(timezone)
+        .take_first()
+    })
+    .unwrap_or_else(|| date_time.assume_timezone_utc(timezone))
+}
+
+pub(crate) fn parse_timezone(name: &str) -> Result<&'static Tz, String> {
+  timezones::get_by_name(name).ok_or_else(|| format!("unknown timezone {name}"))
+}
+
+/// Current time in the timezone of the school.
+pub(crate) fn now(timezone: &Tz) -> OffsetDateTime {
+  OffsetDateTime::now_utc().to_timezone(timezone)
+}
+
+/// Day to show in change notifications, after the last lesson this is the next school day.
+pub(crate) fn notification_date(now: OffsetDateTime, timezone: &Tz, bells: &Bells) -> Date {
+  let now = now.to_timezone(timezone);
+
+  if now.time() >= bells.end_of_day(now.weekday()) {
+    next_school_day(now.date() + time::Duration::days(1))
+  } else {
+    next_school_day(now.date())
+  }
+}
+
+/// Monday of the week of `date`.
+pub(crate) fn monday(date: Date) -> Date {
+  date - time::Duration::days(date.weekday().number_days_from_monday() as i64)
+}
+
+/// Lowercase weekdays like `sunday`.
+fn weekday<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Weekday>, D::Error> {
+  const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+  ];
+
+  let value = String::deserialize(deserializer)?;
+  let mut weekday = Weekday::Monday;
+  for name in WEEKDAYS {
+    if value == name {
+      return Ok(Some(weekday));
+    }
+    weekday = weekday.next();
+  }
+
+  Err(D::Error::unknown_variant(&value, &WEEKDAYS))
+}
+
+pub(crate) fn next_school_day(date: Date) -> Date {
+  match date.weekday() {
+    Weekday::Saturday => date + time::Duration::days(2),
+    Weekday::Sunday => date + time::Duration::days(1),
+    _ => date,
+  }
+}
+
+/// Time until the next multiple of `interval` minutes since midnight in local time.
+pub(crate) fn until_next_execution(now: OffsetDateTime, timezone: &Tz, interval: u64) -> Duration {
+  let now = now.to_timezone(timezone);
+
+  let minutes = u64::from(now.hour()) * 60 + u64::from(now.minute());
+  let seconds = minutes % interval * 60 + u64::from(now.second());
+  let remaining = interval * 60 - seconds;
+
+  // the last slot of a day ends at midnight
+  let until_midnight = (24 * 60 - minutes) * 60 - u64::from(now.second());
+  Duration::from_secs(remaining.min(until_midnight))
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use bszet_davinci::courses::Courses;
+use bszet_davinci::subjects::SubjectDictionary;
+
+/// Classes each chat is subscribed to, stored as json file if a path is given.
+pub struct Subscriptions {
+  path: Option<PathBuf>,
+  state: RwLock<State>,
+}
+
+#[derive(Default)]
+struct State {
+  chats: BTreeMap<i64, BTreeSet<String>>,
+  /// Courses selected by a chat, all lessons are shown without selection
+  courses: BTreeMap<i64, Courses>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredState {
+  chats: BTreeMap<i64, BTreeSet<String>>,
+  courses: BTreeMap<i64, StoredCourses>,
+}
+
+/// Subjects are stored by id, their labels may change.
+#[derive(Serialize, Deserialize)]
+struct StoredCourses {
+  subjects: Vec<String>,
+  group: Option<String>,
+}
+
+impl From<&State> for StoredState {
+  fn from(state: &State) -> Self {
+    Self {
+      chats: state.chats.clone(),
+      courses: state
+        .courses
+        .iter()
+        .map(|(chat, courses)| {
+          let stored = StoredCourses {
+            subjects: courses.subject_ids(),
+            group: courses.group.clone(),
+          };
+          (*chat, stored)
+        })
+        .collect(),
+    }
+  }
+}
+
+impl StoredState {
+  /// Courses unknown to the subject dictionary are dropped, it may have changed since.
+  fn restore(self, subjects: &SubjectDictionary) -> State {
+    let mut courses = BTreeMap::new();
+
+    for (chat, selection) in self.courses {
+      let (selection, unknown) = Courses::from_ids(&selection.subjects, selection.group, subjects);
+
+      if !unknown.is_empty() {
+        warn!(
+          "Dropped unknown courses {} of chat {}",
+          unknown.join(", "),
+          chat
+        );
+      }
+      if !selection.is_empty() {
+        courses.insert(chat, selection);
+      }
+    }
+
+    State {
+      chats: self.chats,
+      courses,
+    }
+  }
+}
+
+impl Subscriptions {
+  /// Loads the stored subscriptions, `seed` is only used if there are none stored yet.
+  pub async fn load(
+    path: Option<PathBuf>,
+    seed: impl IntoIterator<Item = (i64, String)>,
+    subjects: &SubjectDictionary,
+  ) -> anyhow::Result<Self> {
+    let stored = match &path {
+      Some(path) if fs::try_exists(path).await? => {
+        let stored = serde_json::from_slice::<StoredState>(&fs::read(path).await?)?;
+        Some(stored.restore(subjects))
+      }
+      _ => None,
+    };
+
+    let subscriptions = Self {
+      path,
+      state: RwLock::new(State::default()),
+    };
+
+    match stored {
+      Some(state) => *subscriptions.state.write().await = state,
+      None => {
+        info!("No stored subscriptions, using configured chats");
+        for (chat, class) in seed {
+          subscriptions.subscribe(chat, class).await?;
+        }
+      }
+    }
+
+    Ok(subscriptions)
+  }
+
+  /// Returns `false` if the chat was already subscribed to the class.
+  pub async fn subscribe(&self, chat: i64, class: String) -> anyhow::Result<bool> {
+    let mut state = self.state.write().await;
+    let added = state.chats.entry(chat).or_default().insert(class);
+
+    if added {
+      self.save(&state).await?;
+    }
+
+    Ok(added)
+  }
+
+  /// Unsubscribes from `class` or from all classes if `None`, returns the removed classes.
+  pub async fn unsubscribe(&self, chat: i64, class: Option<&str>) -> anyhow::Result<Vec<String>> {
+    let mut state = self.state.write().await;
+
+    let removed = match (state.chats.get_mut(&chat), class) {
+      (None, _) => Vec::new(),
+      (Some(classes), Some(class)) => classes.take(class).into_iter().collect(),
+      (Some(classes), None) => std::mem::take(classes).into_iter().collect(),
+    };
+
+    if state
+      .chats
+      .get(&chat)
+      .map(BTreeSet::is_empty)
+      .unwrap_or(false)
+    {
+      state.chats.remove(&chat);
+      state.courses.remove(&chat);
+    }
+
+    if !removed.is_empty() {
+      self.save(&state).await?;
+    }
+
+    Ok(removed)
+  }
+
+  /// Selects the courses of the chat, an empty selection shows all lessons again.
+  pub async fn select_courses(&self, chat: i64, courses: Courses) -> anyhow::Result<()> {
+    let mut state = self.state.write().await;
+
+    if courses.is_empty() {
+      state.courses.remove(&chat);
+    } else {
+      state.courses.insert(chat, courses);
+    }
+
+    self.save(&state).await
+  }
+
+  pub async fn courses(&self, chat: i64) -> Courses {
+    self
+      .state
+      .read()
+      .await
+      .courses
+      .get(&chat)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub async fn classes(&self, chat: i64) -> Vec<String> {
+    self
+      .state
+      .read()
+      .await
+      .chats
+      .get(&chat)
+      .map(|classes| classes.iter().cloned().collect())
+      .unwrap_or_default()
+  }
+
+  pub async fn chats(&self, class: &str) -> Vec<i64> {
+    self
+      .state
+      .read()
+      .await
+      .chats
+      .iter()
+      .filter(|(_, classes)| classes.contains(class))
+      .map(|(chat, _)| *chat)
+      .collect()
+  }
+
+  async fn save(&self, state: &State) -> anyhow::Result<()> {
+    if let Some(path) = &self.path {
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+      }
+
+      let tmp = path.with_extension("tmp");
+      fs::write(&tmp, serde_json::to_vec(&StoredState::from(state))?).await?;
+      fs::rename(&tmp, path).await?;
+    }
+
+    Ok(())
+  }
+}
@@ -1,6 +1,34 @@
+use std::str::FromStr;
 use std::time::Duration;
 
-use crate::format_duration;
+use time::macros::{date, datetime, time};
+use time::{Date, Month, OffsetDateTime, Weekday};
+
+use bszet_davinci::class::{Class, ClassRegistry};
+use bszet_davinci::courses::Courses;
+use bszet_davinci::iteration::{Holiday, IterationCalendar};
+use bszet_davinci::subjects::SubjectDictionary;
+use bszet_davinci::timetable::{Lesson, Subject};
+use bszet_davinci::AppliedDay;
+
+use crate::api::calendar::{lesson_events, render, school_days, Feed};
+use crate::api::davinci::parse_iso_week;
+use crate::ascii::table;
+use crate::bells::{Bell, Bells};
+use crate::bot::Command;
+use crate::message::{escape, next_lesson};
+use crate::recipients::RecipientsFile;
+use crate::schedule::{
+  notification_date, parse_timezone, until_next_execution, Digest, DigestDay, Overview, Schedule,
+  Target,
+};
+use crate::subscriptions::Subscriptions;
+use crate::{format_duration, week_plan, Chat};
+
+/// Known subject with the labels of the shipped dictionary.
+fn subject(id: &str) -> Subject {
+  SubjectDictionary::default().subject(id).unwrap()
+}
 
 #[test]
 fn test_format_duration() {
@@ -13,3 +41,683 @@ fn test_format_duration() {
     format_duration(Duration::from_secs(60 * 60))
   );
 }
+
+#[test]
+fn test_escape() {
+  assert_eq!(
+    "Unbekannter Kurs LK\\_Ma\\*, Q\\[1] \\`x",
+    escape("Unbekannter Kurs LK_Ma*, Q[1] `x")
+  );
+  assert_eq!("IGD 21, Lehrer: Mü", escape("IGD 21, Lehrer: Mü"));
+}
+
+#[test]
+fn test_parse_chat() -> anyhow::Result<()> {
+  assert_eq!(
+    Chat {
+      id: -734603836,
+      classes: vec![]
+    },
+    Chat::from_str("-734603836")?
+  );
+  assert_eq!(
+    Chat {
+      id: -734603836,
+      classes: vec!["IGD 21".to_string()]
+    },
+    Chat::from_str("-734603836:IGD 21")?
+  );
+  assert_eq!(
+    Chat {
+      id: -734603836,
+      classes: vec!["IGD21".to_string(), "IGD22".to_string()]
+    },
+    Chat::from_str("-734603836:IGD21+IGD22")?
+  );
+  assert!(Chat::from_str("IGD21").is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_parse_command() {
+  assert_eq!(
+    Some(Command::Subscribe(Some("IGD 21"))),
+    Command::parse("/subscribe IGD 21")
+  );
+  assert_eq!(
+    Some(Command::Unsubscribe(None)),
+    Command::parse("/unsubscribe@bszet_mind_bot")
+  );
+  assert_eq!(Some(Command::Today(None)), Command::parse(" /today "));
+  assert_eq!(
+    Some(Command::Courses(Some("LK-Ma, En"))),
+    Command::parse("/courses LK-Ma, En")
+  );
+  assert_eq!(
+    Some(Command::Next(Some("IGD21"))),
+    Command::parse("/next IGD21")
+  );
+  assert_eq!(Some(Command::Help), Command::parse("/start"));
+  assert_eq!(None, Command::parse("hello"));
+}
+
+#[tokio::test]
+async fn test_subscriptions() -> anyhow::Result<()> {
+  let path = std::env::temp_dir().join(format!("bszet-mind-{}.json", std::process::id()));
+  let subjects = SubjectDictionary::default();
+
+  let subscriptions =
+    Subscriptions::load(Some(path.clone()), [(1, "IGD21".to_string())], &subjects).await?;
+  assert!(subscriptions.subscribe(2, "IGD21".to_string()).await?);
+  assert!(!subscriptions.subscribe(2, "IGD21".to_string()).await?);
+  assert!(subscriptions.subscribe(2, "IGD22".to_string()).await?);
+  assert_eq!(vec![1, 2], subscriptions.chats("IGD21").await);
+
+  subscriptions
+    .select_courses(2, Courses::parse("LK-Ma, Gruppe 2", &subjects)?)
+    .await?;
+
+  // stored subscriptions take precedence over the seed
+  let subscriptions =
+    Subscriptions::load(Some(path.clone()), [(3, "IGD21".to_string())], &subjects).await?;
+  assert_eq!(vec![1, 2], subscriptions.chats("IGD21").await);
+  assert_eq!(
+    "LK-Ma, Gruppe 2",
+    subscriptions.courses(2).await.to_string()
+  );
+  assert!(subscriptions.courses(1).await.is_empty());
+  assert_eq!(
+    vec!["IGD22".to_string()],
+    subscriptions.unsubscribe(2, Some("IGD22")).await?
+  );
+  assert_eq!(
+    vec!["IGD21".to_string()],
+    subscriptions.unsubscribe(2, None).await?
+  );
+  assert!(subscriptions.classes(2).await.is_empty());
+  assert!(subscriptions.courses(2).await.is_empty());
+
+  // courses are stored by id, unknown ones are dropped
+  subscriptions
+    .select_courses(5, Courses::parse("LK-Ma, En", &subjects)?)
+    .await?;
+  let stored = std::fs::read_to_string(&path)?;
+  assert!(stored.contains(r#""subjects":["MathAdvanced","EnglishBasic"]"#));
+  std::fs::write(&path, stored.replace("EnglishBasic", "Sanskrit"))?;
+  let subscriptions = Subscriptions::load(Some(path.clone()), [], &subjects).await?;
+  assert_eq!("LK-Ma", subscriptions.courses(5).await.to_string());
+
+  std::fs::remove_file(path)?;
+
+  Ok(())
+}
+
+#[test]
+fn test_recipients() -> anyhow::Result<()> {
+  let mut classes = ClassRegistry::default();
+  classes.insert(Class::new("IGD21".to_string(), vec!["IGD 21".to_string()]))?;
+  classes.insert(Class::new("IGD22".to_string(), vec![]))?;
+
+  let recipients = serde_json::from_str::<RecipientsFile>(
+    r#"{"recipients": [
+      {"name": "ntfy", "classes": ["IGD 21"], "notifier": {"type": "ntfy", "url": "https://ntfy.sh/igd21"}},
+      {"name": "hook", "classes": ["IGD21", "IGD22"], "notifier": {"type": "webhook", "url": "https://example.com/hook"}}
+    ]}"#,
+  )?
+  .into_recipients(&classes)?;
+
+  let igd21 = classes.resolve("IGD21").unwrap();
+  let igd22 = classes.resolve("IGD22").unwrap();
+  assert_eq!(
+    vec!["ntfy", "hook"],
+    recipients
+      .of(igd21)
+      .map(|recipient| recipient.name.as_str())
+      .collect::<Vec<&str>>()
+  );
+  assert_eq!(1, recipients.of(igd22).count());
+
+  assert!(serde_json::from_str::<RecipientsFile>(
+    r#"{"recipients": [{"name": "hook", "classes": ["IGD23"], "notifier": {"type": "webhook", "url": "https://example.com/hook"}}]}"#,
+  )?
+  .into_recipients(&classes)
+  .is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_daily_digest_around_dst() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+  let digest = Schedule::default().digests[0];
+  let due = |now: OffsetDateTime| digest.is_due(now - time::Duration::minutes(15), now, berlin);
+
+  // CET, UTC+1
+  assert!(due(datetime!(2024-03-30 14:00 UTC)));
+  assert!(due(datetime!(2024-03-30 14:14:59 UTC)));
+  assert!(!due(datetime!(2024-03-30 14:15 UTC)));
+  assert!(!due(datetime!(2024-03-30 15:00 UTC)));
+
+  // CEST, UTC+2 from 2024-03-31 02:00 local time
+  assert!(due(datetime!(2024-03-31 13:05 UTC)));
+  assert!(!due(datetime!(2024-03-31 14:05 UTC)));
+
+  // back to CET from 2023-10-29 03:00 local time
+  assert!(due(datetime!(2023-10-28 13:05 UTC)));
+  assert!(!due(datetime!(2023-10-29 13:05 UTC)));
+  assert!(due(datetime!(2023-10-29 14:05 UTC)));
+
+  Ok(())
+}
+
+#[test]
+fn test_notification_date_around_dst() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+  let bells = Bells {
+    lessons: vec![Bell {
+      lesson: 1,
+      from: time!(13:30),
+      to: time!(15:00),
+    }],
+    tuesday: vec![Bell {
+      lesson: 1,
+      from: time!(11:10),
+      to: time!(12:40),
+    }],
+    ..Bells::default()
+  };
+
+  // 14:59 and 15:00 CET
+  assert_eq!(
+    date!(2023 - 10 - 30),
+    notification_date(datetime!(2023-10-30 13:59 UTC), berlin, &bells)
+  );
+  assert_eq!(
+    date!(2023 - 10 - 31),
+    notification_date(datetime!(2023-10-30 14:00 UTC), berlin, &bells)
+  );
+
+  // 15:00 CEST on friday shows monday
+  assert_eq!(
+    date!(2024 - 04 - 08),
+    notification_date(datetime!(2024-04-05 13:00 UTC), berlin, &bells)
+  );
+  assert_eq!(
+    date!(2024 - 04 - 05),
+    notification_date(datetime!(2024-04-05 12:59 UTC), berlin, &bells)
+  );
+
+  // already monday in Berlin while it is still sunday in UTC
+  assert_eq!(
+    date!(2024 - 04 - 01),
+    notification_date(datetime!(2024-03-31 22:30 UTC), berlin, &bells)
+  );
+  assert_eq!(
+    date!(2023 - 10 - 30),
+    notification_date(datetime!(2023-10-29 23:30 UTC), berlin, &bells)
+  );
+
+  // lessons end earlier on tuesdays
+  assert_eq!(
+    date!(2023 - 10 - 31),
+    notification_date(datetime!(2023-10-31 11:39 UTC), berlin, &bells)
+  );
+  assert_eq!(
+    date!(2023 - 11 - 01),
+    notification_date(datetime!(2023-10-31 11:40 UTC), berlin, &bells)
+  );
+
+  Ok(())
+}
+
+#[test]
+fn test_until_next_execution() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+
+  assert_eq!(
+    Duration::from_secs(15 * 60),
+    until_next_execution(datetime!(2024-03-31 00:45 UTC), berlin, 15)
+  );
+  // the hour from 02:00 to 03:00 does not exist on 2024-03-31
+  assert_eq!(
+    Duration::from_secs(30),
+    until_next_execution(datetime!(2024-03-31 00:59:30 UTC), berlin, 15)
+  );
+  assert_eq!(
+    Duration::from_secs(7 * 60),
+    until_next_execution(datetime!(2023-10-29 01:08 UTC), berlin, 15)
+  );
+
+  // the last slot of the day is shorter if the interval does not divide a day
+  assert_eq!(
+    Duration::from_secs(60),
+    until_next_execution(datetime!(2024-04-02 21:59 UTC), berlin, 7)
+  );
+
+  assert!(parse_timezone("Europe/Bautzen").is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_schedule() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+  let schedule = serde_json::from_str::<Schedule>(
+    r#"{
+      "interval": 10,
+      "digests": [{"time": "06:30", "day": "today"}, {"time": "18:00"}],
+      "quiet_hours": {"from": "22:00", "to": "06:00"},
+      "skip_weekends": true,
+      "skip_holidays": true,
+      "chats": [{"id": -734603836, "digests": [{"time": "07:00", "day": "today"}]}]
+    }"#,
+  )?;
+  schedule.validate()?;
+
+  assert_eq!(
+    vec![
+      Digest {
+        time: time!(06:30),
+        day: DigestDay::Today,
+        weekday: None,
+      },
+      Digest {
+        time: time!(18:00),
+        day: DigestDay::Next,
+        weekday: None,
+      }
+    ],
+    schedule.digests(Target::Recipient("hook"))
+  );
+  assert_eq!(1, schedule.digests(Target::Chat(-734603836)).len());
+  assert_eq!(2, schedule.digests(Target::Chat(1)).len());
+  assert_eq!(3, schedule.all_digests().count());
+  // targets are remembered by name when notifying them failed
+  assert_eq!("telegram chat 1", Target::Chat(1).to_string());
+  assert_eq!("hook", Target::Recipient("hook").to_string());
+
+  // friday evening previews monday
+  let preview = schedule.digests[1];
+  assert_eq!(
+    date!(2024 - 04 - 08),
+    preview.date(datetime!(2024-04-05 16:00 UTC), berlin)
+  );
+  assert_eq!(
+    date!(2024 - 04 - 05),
+    schedule.digests[0].date(datetime!(2024-04-05 04:30 UTC), berlin)
+  );
+
+  // quiet hours over midnight
+  assert!(schedule.is_quiet(datetime!(2024-04-05 20:00 UTC), berlin));
+  assert!(schedule.is_quiet(datetime!(2024-04-06 03:59 UTC), berlin));
+  assert!(!schedule.is_quiet(datetime!(2024-04-06 04:00 UTC), berlin));
+  assert!(!schedule.is_quiet(datetime!(2024-04-05 19:59 UTC), berlin));
+
+  let calendar = IterationCalendar::new(
+    Date::from_calendar_date(2023, Month::August, 21)?,
+    None,
+    1,
+    vec![Holiday {
+      name: None,
+      from: date!(2024 - 03 - 28),
+      to: date!(2024 - 04 - 05),
+    }],
+  );
+  assert!(schedule.is_day_off(datetime!(2024-04-06 10:00 UTC), berlin, &calendar));
+  assert!(schedule.is_day_off(datetime!(2024-04-02 10:00 UTC), berlin, &calendar));
+  assert!(!schedule.is_day_off(datetime!(2024-04-08 10:00 UTC), berlin, &calendar));
+  assert!(!Schedule::default().is_day_off(datetime!(2024-04-06 10:00 UTC), berlin, &calendar));
+
+  for invalid in [
+    r#"{"interval": 0}"#,
+    r#"{"quiet_hours": {"from": "22:00", "to": "22:00"}}"#,
+    r#"{"chats": [{"id": 1, "digests": []}, {"id": 1, "digests": []}]}"#,
+  ] {
+    assert!(serde_json::from_str::<Schedule>(invalid)?
+      .validate()
+      .is_err());
+  }
+  assert!(serde_json::from_str::<Schedule>(r#"{"digests": [{"time": "25:00"}]}"#).is_err());
+  assert!(serde_json::from_str::<Schedule>(
+    r#"{"digests": [{"time": "18:00", "weekday": "Sunday"}]}"#
+  )
+  .is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_weekly_digest() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+  let schedule = serde_json::from_str::<Schedule>(
+    r#"{"digests": [{"time": "18:00", "day": "week", "weekday": "sunday"}]}"#,
+  )?;
+  let digest = schedule.digests[0];
+  assert_eq!(Some(Weekday::Sunday), digest.weekday);
+
+  // sunday 18:00 CEST
+  assert!(digest.is_due(
+    datetime!(2024-04-07 15:55 UTC),
+    datetime!(2024-04-07 16:05 UTC),
+    berlin
+  ));
+  assert!(!digest.is_due(
+    datetime!(2024-04-06 15:55 UTC),
+    datetime!(2024-04-06 16:05 UTC),
+    berlin
+  ));
+  assert_eq!(
+    Overview::Week(date!(2024 - 04 - 08)),
+    digest.overview(datetime!(2024-04-07 16:00 UTC), berlin)
+  );
+  // during the week it is the current week
+  assert_eq!(
+    Overview::Week(date!(2024 - 04 - 08)),
+    digest.overview(datetime!(2024-04-10 16:00 UTC), berlin)
+  );
+  assert_eq!(
+    Overview::Day(date!(2024 - 04 - 08)),
+    Schedule::default().digests[0].overview(datetime!(2024-04-07 16:00 UTC), berlin)
+  );
+  // undelivered digests are retried until the school week is over
+  assert!(!Overview::Week(date!(2024 - 04 - 08)).is_over(date!(2024 - 04 - 12)));
+  assert!(Overview::Week(date!(2024 - 04 - 08)).is_over(date!(2024 - 04 - 13)));
+  assert!(!Overview::Day(date!(2024 - 04 - 08)).is_over(date!(2024 - 04 - 08)));
+  assert!(Overview::Day(date!(2024 - 04 - 08)).is_over(date!(2024 - 04 - 09)));
+
+  Ok(())
+}
+
+#[test]
+fn test_week_plan() -> anyhow::Result<()> {
+  assert_eq!(Some(date!(2023 - 09 - 04)), parse_iso_week("2023-W36"));
+  assert_eq!(Some(date!(2024 - 12 - 30)), parse_iso_week("2025w01"));
+  assert_eq!(None, parse_iso_week("2023-W54"));
+  assert_eq!(None, parse_iso_week("2023-09-04"));
+
+  let lesson = |lesson, subject, notice: Option<&str>| Lesson {
+    lesson,
+    subject,
+    planned: None,
+    iteration: None,
+    place: Some("B11".to_string()),
+    teachers: vec![],
+    group: None,
+    notice: notice.map(str::to_string),
+  };
+  let class = Class {
+    name: "IGD21".to_string(),
+    aliases: vec![],
+  };
+  let days = [
+    AppliedDay {
+      date: date!(2023 - 09 - 04),
+      iteration: Some(1),
+      lessons: vec![
+        lesson(
+          1,
+          Subject::Cancel(Box::new(subject("GermanBasic"))),
+          Some("Aufgaben im Lernraum"),
+        ),
+        lesson(3, subject("MathBasic"), None),
+        lesson(3, subject("EnglishBasic"), None),
+      ],
+    },
+    AppliedDay {
+      date: date!(2023 - 09 - 05),
+      iteration: None,
+      lessons: vec![],
+    },
+  ];
+
+  let courses = Courses::parse("En", &SubjectDictionary::default())?;
+  let week = week_plan(&class, date!(2023 - 09 - 04), &courses, &days);
+  assert_eq!("En", week.courses);
+  assert_eq!("Monday 04.09.", week.days[0].title);
+  let cells = &week.days[0].cells;
+  assert_eq!(3, cells.len());
+  assert_eq!(vec!["(D) B11", "Aufgaben im Lernraum"], cells[0].lines);
+  assert!(cells[0].changed);
+  assert!(cells[1].lines.is_empty());
+  assert_eq!(vec!["Ma B11", "En B11"], cells[2].lines);
+  assert!(!cells[2].changed);
+  assert_eq!(3, week.days[1].cells.len());
+
+  Ok(())
+}
+
+#[test]
+fn test_digest_skipped_by_dst() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").map_err(anyhow::Error::msg)?;
+  let digest = Digest {
+    time: time!(02:30),
+    day: DigestDay::Today,
+    weekday: None,
+  };
+
+  // 02:30 does not exist on 2024-03-31, the digest is sent at 03:30 instead
+  assert!(!digest.is_due(
+    datetime!(2024-03-31 00:45 UTC),
+    datetime!(2024-03-31 01:00 UTC),
+    berlin
+  ));
+  assert!(digest.is_due(
+    datetime!(2024-03-31 01:15 UTC),
+    datetime!(2024-03-31 01:30 UTC),
+    berlin
+  ));
+  // at the end of the last run of a day the next day has begun
+  assert!(digest.is_due(
+    datetime!(2024-04-01 00:25 UTC),
+    datetime!(2024-04-01 00:40 UTC),
+    berlin
+  ));
+
+  Ok(())
+}
+
+#[test]
+fn test_ascii_table() {
+  let lesson = |lesson, subject, place: &str, teachers: &[&str], notice: Option<&str>| Lesson {
+    lesson,
+    subject,
+    planned: None,
+    iteration: None,
+    place: Some(place.to_string()),
+    teachers: teachers.iter().map(|teacher| teacher.to_string()).collect(),
+    group: None,
+    notice: notice.map(str::to_string),
+  };
+
+  assert_eq!(
+    "1 Ch B9   Sch\n2 Ph B112 Mül, Kol vertreten",
+    table(vec![
+      lesson(1, subject("Chemistry"), "B9", &["Sch"], None),
+      lesson(
+        2,
+        subject("Physics"),
+        "B112",
+        &["Mül", "Kol"],
+        Some("vertreten")
+      ),
+    ])
+  );
+
+  // the teacher column is left out if the timetable has none
+  assert_eq!(
+    "1 Ch B9\n2 Ph B112",
+    table(vec![
+      lesson(1, subject("Chemistry"), "B9", &[], None),
+      lesson(2, subject("Physics"), "B112", &[], None),
+    ])
+  );
+}
+
+#[test]
+fn test_calendar() -> anyhow::Result<()> {
+  let berlin = parse_timezone("Europe/Berlin").unwrap();
+  let lesson = |lesson, planned: &str, applied, place: Option<&str>, notice: Option<&str>| Lesson {
+    lesson,
+    subject: applied,
+    planned: Some(subject(planned)),
+    iteration: None,
+    place: place.map(str::to_string),
+    teachers: vec!["Sch".to_string()],
+    group: None,
+    notice: notice.map(str::to_string),
+  };
+
+  let calendar = IterationCalendar::new(
+    date!(2023 - 08 - 21),
+    None,
+    1,
+    vec![Holiday {
+      name: None,
+      from: date!(2023 - 10 - 03),
+      to: date!(2023 - 10 - 03),
+    }],
+  );
+  assert_eq!(
+    vec![
+      date!(2023 - 09 - 29),
+      date!(2023 - 10 - 02),
+      date!(2023 - 10 - 04)
+    ],
+    school_days(&calendar, date!(2023 - 09 - 29), 3)
+  );
+
+  let events = lesson_events(
+    "IGD21",
+    date!(2023 - 10 - 30),
+    &[
+      lesson(1, "MathBasic", subject("MathBasic"), Some("B11"), None),
+      lesson(
+        3,
+        "Chemistry",
+        Subject::Cancel(Box::new(subject("Chemistry"))),
+        None,
+        Some("Fällt aus; krank"),
+      ),
+      lesson(7, "Physics", subject("Physics"), None, None),
+    ],
+    &Bells::default(),
+    berlin,
+  );
+  assert_eq!(2, events.len());
+  // the lesson keeps its identity when cancelled
+  assert_eq!("20231030-IGD21-3-Chemistry@bszet-mind", events[1].uid);
+
+  // replacements keep it too, an addition of the same subject is another lesson
+  let changed = lesson_events(
+    "IGD21",
+    date!(2023 - 10 - 30),
+    &[
+      lesson(3, "Chemistry", subject("Physics"), None, None),
+      Lesson {
+        planned: None,
+        ..lesson(3, "Chemistry", subject("Chemistry"), None, None)
+      },
+    ],
+    &Bells::default(),
+    berlin,
+  );
+  assert_eq!(events[1].uid, changed[0].uid);
+  assert_eq!(
+    "20231030-IGD21-3-added-Chemistry@bszet-mind",
+    changed[1].uid
+  );
+
+  let ics = render("IGD21", &events, datetime!(2023-10-29 12:00 UTC));
+  assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+  assert!(ics.ends_with("END:VCALENDAR\r\n"));
+  // winter time after the switch on sunday
+  assert!(ics.contains("DTSTART:20231030T063000Z\r\nDTEND:20231030T080000Z\r\n"));
+  assert!(ics.contains("SUMMARY:Mathematik\r\nLOCATION:B11\r\n"));
+  assert!(ics.contains("DESCRIPTION:Sch\\nFällt aus\\; krank\r\nSTATUS:CANCELLED\r\n"));
+  assert_eq!(1, ics.matches("STATUS:CONFIRMED").count());
+
+  Ok(())
+}
+
+#[test]
+fn test_calendar_feeds() -> anyhow::Result<()> {
+  let feed = Feed::from_str(" IGD21 : 3f9c2a7e41d8b605 ")?;
+  assert_eq!("IGD21", feed.class);
+  assert!(feed.authorizes("IGD21", "3f9c2a7e41d8b605"));
+  assert!(!feed.authorizes("IGD21", "3f9c2a7e41d8b606"));
+  assert!(!feed.authorizes("IGD21", "3f9c2a7e41d8b60"));
+  assert!(!feed.authorizes("IGD22", "3f9c2a7e41d8b605"));
+
+  assert!(Feed::from_str("IGD21").is_err());
+  assert!(Feed::from_str("IGD21:secret").is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_bells() -> anyhow::Result<()> {
+  let bells = Bells::load(
+    &std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+      .parent()
+      .unwrap()
+      .join("bells.toml"),
+  )?;
+  assert_eq!(Bells::default().lessons, bells.lessons);
+
+  let bells = Bells {
+    friday: vec![Bell {
+      lesson: 3,
+      from: time!(11:00),
+      to: time!(12:30),
+    }],
+    ..Bells::default()
+  };
+  assert!(bells.validate().is_ok());
+  assert_eq!(time!(11:10), bells.get(Weekday::Thursday, 3).unwrap().from);
+  assert_eq!(time!(11:00), bells.get(Weekday::Friday, 3).unwrap().from);
+  assert_eq!(time!(16:20), bells.end_of_day(Weekday::Friday));
+
+  let lesson = |lesson, subject, place: &str| Lesson {
+    lesson,
+    subject,
+    planned: None,
+    iteration: None,
+    place: Some(place.to_string()),
+    teachers: vec![],
+    group: None,
+    notice: None,
+  };
+  let day = [
+    lesson(1, subject("MathBasic"), "B11"),
+    lesson(2, Subject::Cancel(Box::new(subject("Physics"))), "B112"),
+    lesson(3, subject("EnglishBasic"), "A102"),
+    lesson(3, subject("French"), "A103"),
+  ];
+  assert_eq!(
+    Some("Nächste Stunde in 20 Minuten: Ma in B11".to_string()),
+    next_lesson(&bells, &day, datetime!(2023-11-02 07:10:30))
+  );
+  // cancelled lessons are skipped
+  assert_eq!(
+    Some("Nächste Stunde in 10 Minuten: En in A102, Frz in A103".to_string()),
+    next_lesson(&bells, &day, datetime!(2023-11-02 11:00))
+  );
+  assert_eq!(
+    Some("Nächste Stunde in 40 Minuten: En in A102, Frz in A103".to_string()),
+    next_lesson(&bells, &day, datetime!(2023-11-03 10:20))
+  );
+  assert_eq!(None, next_lesson(&bells, &day, datetime!(2023-11-02 11:10)));
+
+  let invalid = Bells {
+    lessons: vec![Bell {
+      lesson: 1,
+      from: time!(9:00),
+      to: time!(7:30),
+    }],
+    ..Bells::default()
+  };
+  assert!(invalid.validate().is_err());
+
+  Ok(())
+}
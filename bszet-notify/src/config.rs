@@ -0,0 +1,55 @@
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::email::Email;
+use crate::matrix::Matrix;
+use crate::push::{Gotify, Ntfy};
+use crate::webhook::Webhook;
+use crate::Notifier;
+
+/// Configuration of a notification backend, selected by the `type` field.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum NotifierConfig {
+  Webhook {
+    url: Url,
+  },
+  Matrix {
+    homeserver: Url,
+    access_token: String,
+    room_id: String,
+  },
+  Email {
+    smtp_url: String,
+    from: String,
+    to: Vec<String>,
+  },
+  Ntfy {
+    url: Url,
+    token: Option<String>,
+  },
+  Gotify {
+    url: Url,
+    token: String,
+  },
+}
+
+impl NotifierConfig {
+  pub fn build(&self) -> anyhow::Result<Box<dyn Notifier>> {
+    Ok(match self {
+      Self::Webhook { url } => Box::new(Webhook::new(url.clone())),
+      Self::Matrix {
+        homeserver,
+        access_token,
+        room_id,
+      } => Box::new(Matrix::new(
+        homeserver.clone(),
+        access_token.clone(),
+        room_id.clone(),
+      )),
+      Self::Email { smtp_url, from, to } => Box::new(Email::new(smtp_url, from, to)?),
+      Self::Ntfy { url, token } => Box::new(Ntfy::new(url.clone(), token.clone())),
+      Self::Gotify { url, token } => Box::new(Gotify::new(url.clone(), token.clone())),
+    })
+  }
+}
@@ -1,4 +1,53 @@
+use async_trait::async_trait;
+use serde::Serialize;
+
+pub mod config;
+pub mod email;
+pub mod matrix;
+pub mod push;
 pub mod telegram;
+pub mod webhook;
 
 #[cfg(test)]
 mod test;
+
+/// Delivers notifications to a single recipient.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+/// A notification about the substitution plan, backends use the parts they support.
+#[derive(Debug, Default, Serialize)]
+pub struct Notification {
+  pub title: String,
+  /// Markdown formatted text
+  pub text: String,
+  /// PNG images
+  #[serde(skip)]
+  pub images: Vec<Vec<u8>>,
+  pub changes: Vec<ChangeEntry>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChangeEntry {
+  pub kind: ChangeKind,
+  /// ISO 8601 date
+  pub date: String,
+  pub classes: Vec<String>,
+  pub lesson: u8,
+  /// Last lesson, equal to `lesson` unless the change affects several lessons
+  pub until: u8,
+  pub description: String,
+  /// Description of the row before a modification
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub before: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+  Added,
+  Removed,
+  Modified,
+}
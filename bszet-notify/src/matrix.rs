@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::{Notification, Notifier};
+
+/// Sends messages into a room using the Matrix client-server API.
+pub struct Matrix {
+  client: Client,
+  homeserver: Url,
+  access_token: String,
+  room_id: String,
+  transaction: AtomicU64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "msgtype")]
+enum RoomMessage<'a> {
+  #[serde(rename = "m.text")]
+  Text { body: &'a str },
+  #[serde(rename = "m.image")]
+  Image {
+    body: String,
+    url: String,
+    info: ImageInfo,
+  },
+}
+
+#[derive(Debug, Serialize)]
+struct ImageInfo {
+  mimetype: &'static str,
+  size: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+  content_uri: String,
+}
+
+impl Matrix {
+  pub fn new(homeserver: Url, access_token: String, room_id: String) -> Self {
+    let start = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_millis() as u64)
+      .unwrap_or_default();
+
+    Self {
+      client: Client::new(),
+      homeserver,
+      access_token,
+      room_id,
+      transaction: AtomicU64::new(start),
+    }
+  }
+
+  async fn send(&self, message: &RoomMessage<'_>) -> anyhow::Result<()> {
+    // transaction ids have to be unique per access token
+    let transaction = self.transaction.fetch_add(1, Ordering::Relaxed);
+
+    let mut url = self.homeserver.clone();
+    url
+      .path_segments_mut()
+      .map_err(|_| anyhow::anyhow!("Invalid homeserver url {}", self.homeserver))?
+      .pop_if_empty()
+      .extend([
+        "_matrix",
+        "client",
+        "v3",
+        "rooms",
+        &self.room_id,
+        "send",
+        "m.room.message",
+        &format!("bszet-mind-{transaction}"),
+      ]);
+
+    self
+      .client
+      .put(url)
+      .bearer_auth(&self.access_token)
+      .json(message)
+      .send()
+      .await?
+      .error_for_status()?;
+
+    Ok(())
+  }
+
+  async fn upload(&self, file_name: &str, image: &[u8]) -> anyhow::Result<String> {
+    let mut url = self.homeserver.join("_matrix/media/v3/upload")?;
+    url.query_pairs_mut().append_pair("filename", file_name);
+
+    let response = self
+      .client
+      .post(url)
+      .bearer_auth(&self.access_token)
+      .header(CONTENT_TYPE, "image/png")
+      .body(image.to_vec())
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<UploadResponse>()
+      .await?;
+
+    Ok(response.content_uri)
+  }
+}
+
+#[async_trait]
+impl Notifier for Matrix {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+    self
+      .send(&RoomMessage::Text {
+        body: &notification.text,
+      })
+      .await?;
+
+    for (index, image) in notification.images.iter().enumerate() {
+      let file_name = format!("{index}.png");
+      let url = self.upload(&file_name, image).await?;
+
+      self
+        .send(&RoomMessage::Image {
+          body: file_name,
+          url,
+          info: ImageInfo {
+            mimetype: "image/png",
+            size: image.len(),
+          },
+        })
+        .await?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Serialize;
+
+use crate::{Notification, Notifier};
+
+/// Publishes the text to a ntfy topic, e.g. `https://ntfy.sh/my-topic`.
+pub struct Ntfy {
+  client: Client,
+  topic: Url,
+  token: Option<String>,
+}
+
+/// Sends the text as message to a Gotify server.
+pub struct Gotify {
+  client: Client,
+  base: Url,
+  token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GotifyMessage<'a> {
+  title: &'a str,
+  message: &'a str,
+  extras: GotifyExtras,
+}
+
+#[derive(Debug, Serialize)]
+struct GotifyExtras {
+  #[serde(rename = "client::display")]
+  display: GotifyDisplay,
+}
+
+#[derive(Debug, Serialize)]
+struct GotifyDisplay {
+  #[serde(rename = "contentType")]
+  content_type: &'static str,
+}
+
+impl Ntfy {
+  pub fn new(topic: Url, token: Option<String>) -> Self {
+    Self {
+      client: Client::new(),
+      topic,
+      token,
+    }
+  }
+}
+
+impl Gotify {
+  pub fn new(base: Url, token: String) -> Self {
+    Self {
+      client: Client::new(),
+      base,
+      token,
+    }
+  }
+}
+
+#[async_trait]
+impl Notifier for Ntfy {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+    let mut request = self
+      .client
+      .post(self.topic.clone())
+      .header("Title", &notification.title)
+      .header("Markdown", "yes")
+      .body(notification.text.clone());
+
+    if let Some(token) = &self.token {
+      request = request.bearer_auth(token);
+    }
+
+    request.send().await?.error_for_status()?;
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl Notifier for Gotify {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+    let message = GotifyMessage {
+      title: &notification.title,
+      message: &notification.text,
+      extras: GotifyExtras {
+        display: GotifyDisplay {
+          content_type: "text/markdown",
+        },
+      },
+    };
+
+    self
+      .client
+      .post(self.base.join("message")?)
+      .header("X-Gotify-Key", &self.token)
+      .json(&message)
+      .send()
+      .await?
+      .error_for_status()?;
+
+    Ok(())
+  }
+}
@@ -1,7 +1,13 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
 use reqwest::header::{HeaderValue, CONTENT_TYPE};
 use reqwest::multipart::{Form, Part};
 use reqwest::{Client, Url};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{Notification, Notifier};
 
 pub struct Telegram {
   client: Client,
@@ -23,6 +29,7 @@ struct InputMediaPhoto {
   parse_mode: Option<ParseMode>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 struct SendMediaGroupData {
   chat_id: i64,
@@ -41,6 +48,52 @@ struct SendMessageData {
   parse_mode: ParseMode,
 }
 
+#[derive(Debug, Serialize)]
+struct GetUpdatesData {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  offset: Option<i64>,
+  timeout: u64,
+  allowed_updates: Vec<&'static str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+  ok: bool,
+  result: Option<T>,
+  description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Update {
+  pub update_id: i64,
+  pub message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+  pub message_id: i64,
+  pub chat: Chat,
+  pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Chat {
+  pub id: i64,
+}
+
+/// Notifies a single chat, images are sent as media group with the text as caption.
+pub struct TelegramChat {
+  telegram: Arc<Telegram>,
+  chat_id: i64,
+}
+
+/// Long polls updates of the bot, keeping track of already received updates.
+pub struct Poller<'a> {
+  telegram: &'a Telegram,
+  offset: Option<i64>,
+  timeout: u64,
+}
+
 impl Telegram {
   pub fn new(token: &str) -> anyhow::Result<Self> {
     let raw = format!("https://api.telegram.org/bot{token}/");
@@ -70,6 +123,49 @@ impl Telegram {
     Ok(())
   }
 
+  /// Waits up to `timeout` seconds for new updates.
+  pub async fn get_updates(
+    &self,
+    offset: Option<i64>,
+    timeout: u64,
+  ) -> anyhow::Result<Vec<Update>> {
+    let data = GetUpdatesData {
+      offset,
+      timeout,
+      allowed_updates: vec!["message"],
+    };
+
+    let response = self
+      .client
+      .post(self.base.join("getUpdates")?)
+      .json(&data)
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<Response<Vec<Update>>>()
+      .await?;
+
+    match response {
+      Response {
+        ok: true,
+        result: Some(updates),
+        ..
+      } => Ok(updates),
+      Response { description, .. } => Err(anyhow!(
+        "Unable to get updates: {}",
+        description.unwrap_or_default()
+      )),
+    }
+  }
+
+  pub fn poller(&self, timeout: u64) -> Poller<'_> {
+    Poller {
+      telegram: self,
+      offset: None,
+      timeout,
+    }
+  }
+
   pub async fn send_images(
     &self,
     chat_id: i64,
@@ -118,3 +214,44 @@ impl Telegram {
     Ok(())
   }
 }
+
+impl Poller<'_> {
+  /// Returns the messages received since the last call, can be empty if the timeout elapsed.
+  pub async fn next(&mut self) -> anyhow::Result<Vec<Message>> {
+    let updates = self.telegram.get_updates(self.offset, self.timeout).await?;
+
+    if let Some(last) = updates.last() {
+      self.offset = Some(last.update_id + 1);
+    }
+
+    Ok(
+      updates
+        .into_iter()
+        .filter_map(|update| update.message)
+        .collect(),
+    )
+  }
+}
+
+impl TelegramChat {
+  pub fn new(telegram: Arc<Telegram>, chat_id: i64) -> Self {
+    Self { telegram, chat_id }
+  }
+}
+
+#[async_trait]
+impl Notifier for TelegramChat {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+    if notification.images.is_empty() {
+      self
+        .telegram
+        .send_text(self.chat_id, &notification.text)
+        .await
+    } else {
+      self
+        .telegram
+        .send_images(self.chat_id, &notification.text, &notification.images)
+        .await
+    }
+  }
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+
+use crate::{Notification, Notifier};
+
+/// Posts the notification as json, images are not included.
+pub struct Webhook {
+  client: Client,
+  url: Url,
+}
+
+impl Webhook {
+  pub fn new(url: Url) -> Self {
+    Self {
+      client: Client::new(),
+      url,
+    }
+  }
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+  async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+    self
+      .client
+      .post(self.url.clone())
+      .json(notification)
+      .send()
+      .await?
+      .error_for_status()?;
+
+    Ok(())
+  }
+}